@@ -0,0 +1,345 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::DatadogError;
+use crate::format::{color_status, status_severity};
+use crate::logs::DatadogClient;
+use crate::time::validate_time_range;
+
+/// Parameters for counting logs grouped by a facet
+#[derive(Debug, Clone)]
+pub struct LogsAggregateQuery {
+    pub query: String,
+    pub from: String,
+    pub to: String,
+    /// Facet to group by, e.g. `service` or `@http.status_code`
+    pub group_by: String,
+    /// Maximum number of groups to return. None = API default.
+    pub limit: Option<u32>,
+    /// Log indexes to search. Empty = the account's default indexes.
+    pub indexes: Vec<String>,
+    /// Timezone sent as `options.timezone`, e.g. `America/New_York`. None = UTC.
+    pub timezone: Option<String>,
+}
+
+impl LogsAggregateQuery {
+    pub fn new(query: String, from: String, to: String, group_by: String) -> Self {
+        Self {
+            query,
+            from,
+            to,
+            group_by,
+            limit: None,
+            indexes: Vec::new(),
+            timezone: None,
+        }
+    }
+
+    pub fn with_limit(mut self, limit: Option<u32>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Restrict the aggregation to the given log indexes
+    pub fn with_indexes(mut self, indexes: Vec<String>) -> Self {
+        self.indexes = indexes;
+        self
+    }
+
+    /// Have the API interpret the aggregation in `timezone`
+    pub fn with_timezone(mut self, timezone: Option<String>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    fn request(&self) -> AggregateRequest<'_> {
+        AggregateRequest {
+            compute: [Compute {
+                aggregation: "count",
+                compute_type: "total",
+            }],
+            filter: AggregateFilter {
+                query: &self.query,
+                from: &self.from,
+                to: &self.to,
+                indexes: &self.indexes,
+            },
+            group_by: [GroupBy {
+                facet: &self.group_by,
+                limit: self.limit,
+            }],
+            options: self
+                .timezone
+                .as_deref()
+                .map(|timezone| AggregateOptions { timezone }),
+        }
+    }
+}
+
+/// Number of logs for one value of the grouped facet
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LogsAggregateBucket {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Serialize)]
+struct AggregateRequest<'a> {
+    compute: [Compute; 1],
+    filter: AggregateFilter<'a>,
+    group_by: [GroupBy<'a>; 1],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<AggregateOptions<'a>>,
+}
+
+#[derive(Serialize)]
+struct AggregateOptions<'a> {
+    timezone: &'a str,
+}
+
+#[derive(Serialize)]
+struct Compute {
+    aggregation: &'static str,
+    #[serde(rename = "type")]
+    compute_type: &'static str,
+}
+
+#[derive(Serialize)]
+struct AggregateFilter<'a> {
+    query: &'a str,
+    from: &'a str,
+    to: &'a str,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    indexes: &'a [String],
+}
+
+#[derive(Serialize)]
+struct GroupBy<'a> {
+    facet: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct AggregateResponse {
+    data: Option<AggregateData>,
+}
+
+#[derive(Deserialize)]
+struct AggregateData {
+    buckets: Option<Vec<RawBucket>>,
+}
+
+#[derive(Deserialize)]
+struct RawBucket {
+    by: serde_json::Map<String, serde_json::Value>,
+    computes: serde_json::Map<String, serde_json::Value>,
+}
+
+impl DatadogClient {
+    /// Count logs grouped by a facet using the analytics aggregate API.
+    /// Buckets are sorted by count (highest first), then by value.
+    pub fn aggregate_logs(
+        &self,
+        query: &LogsAggregateQuery,
+    ) -> Result<Vec<LogsAggregateBucket>, DatadogError> {
+        validate_time_range(&query.from, &query.to, chrono::Utc::now())
+            .map_err(DatadogError::Config)?;
+
+        let request_body = query.request();
+
+        let url = self.endpoint("/api/v2/logs/analytics/aggregate");
+        let response: AggregateResponse =
+            self.send_json("POST", &url, |client| client.post(&url).json(&request_body))?;
+
+        let buckets = response.data.and_then(|d| d.buckets).unwrap_or_default();
+        Ok(collect_buckets(buckets, &query.group_by))
+    }
+}
+
+fn collect_buckets(raw: Vec<RawBucket>, facet: &str) -> Vec<LogsAggregateBucket> {
+    let mut buckets: Vec<LogsAggregateBucket> = raw
+        .into_iter()
+        .map(|bucket| {
+            let value = match bucket.by.get(facet) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            let count = bucket
+                .computes
+                .get("c0")
+                .and_then(|c| c.as_f64())
+                .unwrap_or(0.0) as u64;
+            LogsAggregateBucket { value, count }
+        })
+        .collect();
+
+    buckets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    buckets
+}
+
+/// Order buckets of a `status` grouping most severe first, then by value
+pub fn sort_status_buckets(buckets: &mut [LogsAggregateBucket]) {
+    buckets.sort_by(|a, b| {
+        (status_severity(&a.value), &a.value).cmp(&(status_severity(&b.value), &b.value))
+    });
+}
+
+/// Client-side count of entries per status, tallied a batch at a time. Statuses are
+/// trimmed and lowercased; entries without one count as "none".
+#[derive(Debug, Clone, Default)]
+pub struct StatusCounts {
+    counts: std::collections::HashMap<String, u64>,
+}
+
+impl StatusCounts {
+    pub fn tally<'a>(&mut self, statuses: impl IntoIterator<Item = Option<&'a str>>) {
+        for status in statuses {
+            let status = status.map_or("none".to_string(), |s| s.trim().to_lowercase());
+            *self.counts.entry(status).or_default() += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The counts as buckets like an aggregation grouped by `status`, sorted with
+    /// `sort_status_buckets`
+    pub fn buckets(&self) -> Vec<LogsAggregateBucket> {
+        let mut buckets: Vec<LogsAggregateBucket> = self
+            .counts
+            .iter()
+            .map(|(value, count)| LogsAggregateBucket {
+                value: value.clone(),
+                count: *count,
+            })
+            .collect();
+        sort_status_buckets(&mut buckets);
+        buckets
+    }
+}
+
+/// Render buckets as a two-column table with counts right-aligned
+pub fn format_aggregate_table(facet: &str, buckets: &[LogsAggregateBucket]) -> String {
+    aggregate_table(facet, buckets, false)
+}
+
+/// Render status buckets like `format_aggregate_table`, coloring each status by level
+pub fn format_status_table(buckets: &[LogsAggregateBucket]) -> String {
+    aggregate_table("status", buckets, true)
+}
+
+fn aggregate_table(facet: &str, buckets: &[LogsAggregateBucket], color_values: bool) -> String {
+    let value_width = buckets
+        .iter()
+        .map(|b| b.value.chars().count())
+        .chain([facet.chars().count()])
+        .max()
+        .unwrap_or(0);
+    let count_width = buckets
+        .iter()
+        .map(|b| b.count.to_string().len())
+        .chain(["count".len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = vec![format!(
+        "{:<vw$}  {:>cw$}",
+        facet,
+        "count",
+        vw = value_width,
+        cw = count_width
+    )];
+    for bucket in buckets {
+        let value = format!("{:<vw$}", bucket.value, vw = value_width);
+        let value = if color_values {
+            color_status(&value).to_string()
+        } else {
+            value
+        };
+        lines.push(format!(
+            "{}  {:>cw$}",
+            value,
+            bucket.count,
+            cw = count_width
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_buckets() -> Vec<RawBucket> {
+        let response: AggregateResponse = serde_json::from_value(serde_json::json!({
+            "data": {
+                "buckets": [
+                    { "by": { "service": "web" }, "computes": { "c0": 12 } },
+                    { "by": { "service": "api" }, "computes": { "c0": 340 } },
+                    { "by": { "service": "db" }, "computes": { "c0": 12 } }
+                ]
+            }
+        }))
+        .unwrap();
+        response.data.unwrap().buckets.unwrap()
+    }
+
+    #[test]
+    fn test_collect_buckets_sorts_by_count_then_value() {
+        let buckets = collect_buckets(raw_buckets(), "service");
+
+        let pairs: Vec<(&str, u64)> = buckets
+            .iter()
+            .map(|b| (b.value.as_str(), b.count))
+            .collect();
+        assert_eq!(pairs, vec![("api", 340), ("db", 12), ("web", 12)]);
+    }
+
+    #[test]
+    fn test_request_includes_timezone_when_set() {
+        let query =
+            LogsAggregateQuery::new("*".into(), "now-1h".into(), "now".into(), "service".into());
+        let body = serde_json::to_value(query.request()).unwrap();
+        assert!(body.get("options").is_none());
+
+        let query = query.with_timezone(Some("Europe/Paris".into()));
+        let body = serde_json::to_value(query.request()).unwrap();
+        assert_eq!(
+            body["options"],
+            serde_json::json!({ "timezone": "Europe/Paris" })
+        );
+    }
+
+    #[test]
+    fn test_format_aggregate_table() {
+        let buckets = collect_buckets(raw_buckets(), "service");
+
+        assert_eq!(
+            format_aggregate_table("service", &buckets),
+            "service  count\napi        340\ndb          12\nweb         12"
+        );
+    }
+
+    #[test]
+    fn test_format_status_table() {
+        colored::control::set_override(false);
+        let mut buckets = vec![
+            LogsAggregateBucket {
+                value: "info".into(),
+                count: 1290,
+            },
+            LogsAggregateBucket {
+                value: "error".into(),
+                count: 12,
+            },
+        ];
+        sort_status_buckets(&mut buckets);
+
+        assert_eq!(
+            format_status_table(&buckets),
+            "status  count\nerror      12\ninfo     1290"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use serde::Deserialize;
+
+use crate::error::DatadogError;
+use crate::logs::DatadogClient;
+
+#[derive(Deserialize)]
+struct ValidateResponse {
+    valid: Option<bool>,
+}
+
+impl DatadogClient {
+    /// Check the API key against the site's `/api/v1/validate` endpoint.
+    /// Returns `Ok(false)` when Datadog rejects the key, and an error for any other failure.
+    pub fn validate_credentials(&self) -> Result<bool, DatadogError> {
+        let url = self.endpoint("/api/v1/validate");
+
+        match self.get_json::<ValidateResponse>(&url) {
+            Ok(response) => Ok(response.valid.unwrap_or(false)),
+            Err(DatadogError::Api { status, .. })
+                if status == reqwest::StatusCode::FORBIDDEN
+                    || status == reqwest::StatusCode::UNAUTHORIZED =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
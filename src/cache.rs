@@ -0,0 +1,271 @@
+use std::fs::{DirBuilder, OpenOptions};
+use std::hash::Hasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use fnv::FnvHasher;
+
+use crate::logs::{DatadogClient, LogEntry, LogsQuery};
+
+/// On-disk cache of log search results, keyed by the search request, site and credentials.
+/// Entries older than the TTL are ignored. On Unix the directory is only accessible to the
+/// current user, and a directory anyone else could read or write is never used.
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ResultCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// Cache in the user's cache directory: `$XDG_CACHE_HOME/datadog-cli`, falling back to
+    /// `~/.cache/datadog-cli`
+    pub fn in_user_cache_dir(ttl: Duration) -> Self {
+        Self::new(user_cache_dir().join("datadog-cli"), ttl)
+    }
+
+    /// Whether results of `query` can be cached. Relative times such as `now-15m` match
+    /// different logs on every run, so both ends of the range must be absolute.
+    pub fn is_cacheable(query: &LogsQuery) -> bool {
+        let absolute = |time: &str| !time.trim().starts_with("now");
+        absolute(&query.from) && absolute(&query.to) && query.cursor.is_none()
+    }
+
+    /// Cached entries for `query` sent by `client`, if stored within the TTL
+    pub fn get(&self, query: &LogsQuery, client: &DatadogClient) -> Option<Vec<LogEntry>> {
+        if !is_private(&self.dir) {
+            return None;
+        }
+        let path = self.path(query, client);
+        let age = std::fs::metadata(&path)
+            .ok()?
+            .modified()
+            .ok()?
+            .elapsed()
+            .ok()?;
+        if age >= self.ttl {
+            return None;
+        }
+
+        let contents = std::fs::read_to_string(&path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Store the complete results of `query` sent by `client`
+    pub fn put(
+        &self,
+        query: &LogsQuery,
+        client: &DatadogClient,
+        entries: &[LogEntry],
+    ) -> io::Result<()> {
+        create_private_dir(&self.dir)?;
+        let contents = serde_json::to_string(entries).map_err(io::Error::other)?;
+        private_file(&self.path(query, client))?.write_all(contents.as_bytes())
+    }
+
+    /// Remove every cached result
+    pub fn clear(&self) -> io::Result<()> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    fn path(&self, query: &LogsQuery, client: &DatadogClient) -> PathBuf {
+        // The request body covers the query, time range, indexes, sort and page size. The
+        // keys keep one org's results from being served to another on the same site.
+        // FNV-1a over the raw bytes is stable across Rust releases, unlike DefaultHasher, so
+        // a toolchain upgrade doesn't invalidate the cache.
+        let limit = query
+            .limit
+            .map_or_else(String::new, |limit| limit.to_string());
+        let mut hasher = FnvHasher::default();
+        for field in [
+            query.request_body().to_string().as_str(),
+            &limit,
+            &client.base_url,
+            &client.api_key,
+            &client.app_key,
+        ] {
+            hasher.write(field.as_bytes());
+            // A byte no field contains, so fields can't run into each other
+            hasher.write_u8(0xff);
+        }
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+}
+
+fn user_cache_dir() -> PathBuf {
+    let non_empty = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+    non_empty("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| non_empty("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Create `dir` (and missing parents) readable only by the current user, and check that an
+/// existing one is private too
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)?;
+
+    if is_private(dir) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} is not owned by the current user or is accessible to others",
+                dir.display()
+            ),
+        ))
+    }
+}
+
+/// Whether `dir` is owned by the current user and closed to everyone else
+#[cfg(unix)]
+fn is_private(dir: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { libc::getuid() };
+    std::fs::symlink_metadata(dir)
+        .is_ok_and(|meta| meta.is_dir() && meta.uid() == uid && meta.mode() & 0o077 == 0)
+}
+
+#[cfg(not(unix))]
+fn is_private(dir: &Path) -> bool {
+    dir.is_dir()
+}
+
+/// Create or truncate `path`, readable and writable only by the current user
+fn private_file(path: &Path) -> io::Result<std::fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn cache(name: &str, ttl: Duration) -> ResultCache {
+        let dir = std::env::temp_dir().join(format!(
+            "datadog-cache-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        ResultCache::new(dir, ttl)
+    }
+
+    fn query(from: &str, to: &str) -> LogsQuery {
+        LogsQuery::new("service:api".into(), from.into(), to.into(), Some(10))
+    }
+
+    fn client(site: &str, api_key: &str) -> DatadogClient {
+        DatadogClient::builder()
+            .api_key(api_key)
+            .app_key("app")
+            .site(site)
+            .build()
+            .unwrap()
+    }
+
+    fn entries() -> Vec<LogEntry> {
+        serde_json::from_value(serde_json::json!([
+            { "id": "a", "attributes": { "message": "boom" } }
+        ]))
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case("2024-01-01T00:00:00+00:00", "2024-01-02T00:00:00+00:00", true)]
+    #[case("1704067200", "1704153600", true)]
+    #[case("now-15m", "now", false)]
+    #[case("2024-01-01T00:00:00+00:00", "now", false)]
+    fn test_is_cacheable(#[case] from: &str, #[case] to: &str, #[case] expected: bool) {
+        assert_eq!(ResultCache::is_cacheable(&query(from, to)), expected);
+    }
+
+    #[test]
+    fn test_round_trip_keyed_by_query_site_and_keys() {
+        let cache = cache("round-trip", Duration::from_secs(60));
+        let query = query("1704067200", "1704153600");
+        let client = client("datadoghq.com", "org-a");
+
+        cache.put(&query, &client, &entries()).unwrap();
+
+        let hit = cache.get(&query, &client).unwrap();
+        assert_eq!(hit[0].id.as_deref(), Some("a"));
+        assert!(
+            cache
+                .get(&query, &self::client("datadoghq.eu", "org-a"))
+                .is_none()
+        );
+        assert!(
+            cache
+                .get(&query, &self::client("datadoghq.com", "org-b"))
+                .is_none()
+        );
+        let other = query.clone().with_indexes(vec!["main".into()]);
+        assert!(cache.get(&other, &client).is_none());
+
+        cache.clear().unwrap();
+        assert!(cache.get(&query, &client).is_none());
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_path_is_stable_across_builds() {
+        let cache = ResultCache::new(PathBuf::from("/cache"), Duration::from_secs(60));
+        let path = cache.path(
+            &query("1704067200", "1704153600"),
+            &client("datadoghq.com", "org-a"),
+        );
+
+        // Pinned so a change to the key (or its hash) is noticed
+        assert_eq!(path, Path::new("/cache/4fa50ea28350944e.json"));
+    }
+
+    #[test]
+    fn test_expired_entries_are_ignored() {
+        let cache = cache("expired", Duration::ZERO);
+        let query = query("1704067200", "1704153600");
+        let client = client("datadoghq.com", "org-a");
+
+        cache.put(&query, &client, &entries()).unwrap();
+
+        assert!(cache.get(&query, &client).is_none());
+        cache.clear().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cache_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let cache = cache("private", Duration::from_secs(60));
+        let query = query("1704067200", "1704153600");
+        let client = client("datadoghq.com", "org-a");
+
+        cache.put(&query, &client, &entries()).unwrap();
+
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&cache.dir), 0o700);
+        assert_eq!(mode(&cache.path(&query, &client)), 0o600);
+
+        // A directory others can write to is neither read from nor written to
+        std::fs::set_permissions(&cache.dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(cache.get(&query, &client).is_none());
+        assert!(cache.put(&query, &client, &entries()).is_err());
+        cache.clear().unwrap();
+    }
+}
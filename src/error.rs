@@ -0,0 +1,146 @@
+use std::fmt;
+
+/// Errors returned by `DatadogClient`
+#[derive(Debug)]
+pub enum DatadogError {
+    /// Missing or invalid client configuration, e.g. credentials
+    Config(String),
+    /// The HTTP request could not be sent
+    Request(reqwest::Error),
+    /// The API responded with a non-success status
+    Api {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    /// The response body could not be parsed
+    Parse(String),
+}
+
+impl fmt::Display for DatadogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatadogError::Config(msg) => write!(f, "{}", msg),
+            DatadogError::Request(e) => write!(f, "Request failed: {}", e),
+            // One layout whether or not the body parsed, e.g. "API error (403 Forbidden): ..."
+            DatadogError::Api { status, body } => match first_api_error(body) {
+                Some(message) => write!(f, "API error ({}): {}", status, message),
+                None => write!(f, "API error ({}): {}", status, body),
+            },
+            DatadogError::Parse(msg) => write!(f, "Failed to parse response: {}", msg),
+        }
+    }
+}
+
+/// Extract the first message from a Datadog `{"errors": [...]}` body. Entries are
+/// usually strings; v2 endpoints may instead return objects with `detail`/`title`.
+fn first_api_error(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let first = value.get("errors")?.as_array()?.first()?;
+
+    let message = match first {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Object(obj) => obj
+            .get("detail")
+            .or_else(|| obj.get("title"))
+            .and_then(|v| v.as_str())?
+            .to_string(),
+        _ => return None,
+    };
+    (!message.is_empty()).then_some(message)
+}
+
+impl DatadogError {
+    /// Whether this is a 400 rejecting the requested page size, which some accounts cap
+    /// below `MAX_PAGE_SIZE`. Only an error naming the `page[limit]` parameter counts, so
+    /// other validation errors aren't retried with smaller pages.
+    pub(crate) fn is_page_limit_error(&self) -> bool {
+        match self {
+            DatadogError::Api { status, body } if *status == reqwest::StatusCode::BAD_REQUEST => {
+                first_api_error(body).is_some_and(|message| message.contains("page[limit]"))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for DatadogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatadogError::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        StatusCode::FORBIDDEN,
+        r#"{"errors": ["invalid app key"]}"#,
+        "API error (403 Forbidden): invalid app key"
+    )]
+    #[case(
+        StatusCode::BAD_REQUEST,
+        r#"{"errors": [{"status": "400", "title": "Bad Request", "detail": "invalid query"}]}"#,
+        "API error (400 Bad Request): invalid query"
+    )]
+    #[case(
+        StatusCode::BAD_GATEWAY,
+        "<html>bad gateway</html>",
+        "API error (502 Bad Gateway): <html>bad gateway</html>"
+    )]
+    #[case(
+        StatusCode::FORBIDDEN,
+        r#"{"errors": []}"#,
+        r#"API error (403 Forbidden): {"errors": []}"#
+    )]
+    fn test_api_error_display(
+        #[case] status: StatusCode,
+        #[case] body: &str,
+        #[case] expected: &str,
+    ) {
+        let error = DatadogError::Api {
+            status,
+            body: body.to_string(),
+        };
+
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        StatusCode::BAD_REQUEST,
+        r#"{"errors": [{"detail": "page[limit] must be at most 1000"}]}"#,
+        true
+    )]
+    #[case(
+        StatusCode::BAD_REQUEST,
+        r#"{"errors": ["page[limit] is too large"]}"#,
+        true
+    )]
+    #[case(StatusCode::BAD_REQUEST, r#"{"errors": ["invalid query"]}"#, false)]
+    #[case(
+        StatusCode::BAD_REQUEST,
+        r#"{"errors": ["query exceeds the length limit for this page"]}"#,
+        false
+    )]
+    #[case(StatusCode::BAD_REQUEST, "page limit", false)]
+    #[case(StatusCode::FORBIDDEN, r#"{"errors": ["page limit exceeded"]}"#, false)]
+    fn test_is_page_limit_error(
+        #[case] status: StatusCode,
+        #[case] body: &str,
+        #[case] expected: bool,
+    ) {
+        let error = DatadogError::Api {
+            status,
+            body: body.to_string(),
+        };
+
+        assert_eq!(error.is_page_limit_error(), expected);
+    }
+}
@@ -1,8 +1,17 @@
-use chrono::{DateTime, Utc};
 use colored::Colorize;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
-use crate::logs::DatadogClient;
+use crate::error::DatadogError;
+use crate::format::{color_status, csv_record, format_table};
+use crate::highlight::highlight_matches;
+use crate::logs::{
+    DatadogClient, EVENTS_SEARCH_PATH, MAX_PAGE_SIZE, SearchSummary, debug_pagination, page_size,
+};
+use crate::time::{
+    DisplayTimezone, TimeFormat, display_timestamp, format_timestamp, validate_time_range,
+};
 
 /// Parameters for an events search query
 #[derive(Debug, Clone)]
@@ -12,6 +21,12 @@ pub struct EventsQuery {
     pub to: String,
     /// Maximum number of events to retrieve. None = fetch all.
     pub limit: Option<u32>,
+    /// Sort order: `timestamp` (oldest first) or `-timestamp` (newest first). None = API default.
+    pub sort: Option<String>,
+    /// Stop after this many pages even if more results remain. None = no cap.
+    pub max_pages: Option<u32>,
+    /// Largest page to request. None = `MAX_PAGE_SIZE`, halved while the API rejects it.
+    pub page_size: Option<u32>,
 }
 
 impl EventsQuery {
@@ -21,8 +36,52 @@ impl EventsQuery {
             from,
             to,
             limit,
+            sort: None,
+            max_pages: None,
+            page_size: None,
         }
     }
+
+    /// Request at most `page_size` events per page, for accounts capped below `MAX_PAGE_SIZE`
+    pub fn with_page_size(mut self, page_size: Option<u32>) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Cap the number of pages requested, independently of `limit`
+    pub fn with_max_pages(mut self, max_pages: Option<u32>) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Query string for a search request to `EVENTS_SEARCH_PATH`, without the page cursor.
+    /// `page_size` defaults to the size derived from `limit`.
+    pub fn request_params(&self, page_size: Option<u32>) -> String {
+        let page_size = page_size.unwrap_or_else(|| self.first_page_size());
+        let mut params = format!(
+            "filter[query]={}&filter[from]={}&filter[to]={}&page[limit]={}",
+            urlencoding::encode(&self.query),
+            urlencoding::encode(&self.from),
+            urlencoding::encode(&self.to),
+            page_size
+        );
+
+        if let Some(ref sort) = self.sort {
+            params.push_str(&format!("&sort={}", urlencoding::encode(sort)));
+        }
+        params
+    }
+
+    /// Number of events requested in the first page
+    pub fn first_page_size(&self) -> u32 {
+        crate::logs::page_size(self.limit, 0).min(self.page_size.unwrap_or(MAX_PAGE_SIZE))
+    }
+
+    /// Sort events by timestamp: `timestamp` for oldest first, `-timestamp` for newest first
+    pub fn with_sort(mut self, sort: Option<String>) -> Self {
+        self.sort = sort;
+        self
+    }
 }
 
 // Internal response structure (includes pagination metadata)
@@ -48,7 +107,7 @@ pub struct EventsSearchResponse {
     pub data: Option<Vec<EventEntry>>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct EventEntry {
     pub id: Option<String>,
     #[serde(rename = "type")]
@@ -56,7 +115,7 @@ pub struct EventEntry {
     pub attributes: EventAttributes,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct EventAttributes {
     pub timestamp: Option<String>,
     pub attributes: Option<EventInnerAttributes>,
@@ -66,7 +125,7 @@ pub struct EventAttributes {
     pub other: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct EventInnerAttributes {
     pub title: Option<String>,
     pub status: Option<String>,
@@ -75,7 +134,7 @@ pub struct EventInnerAttributes {
     pub other: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct EventDetails {
     pub name: Option<String>,
     #[serde(flatten)]
@@ -83,38 +142,86 @@ pub struct EventDetails {
 }
 
 impl DatadogClient {
+    /// Search events and collect every page into a single `Vec`.
+    /// Prefer `search_events` for large result sets, since this holds all entries in memory.
+    pub fn search_events_collect(
+        &self,
+        query: &EventsQuery,
+    ) -> Result<Vec<EventEntry>, DatadogError> {
+        let mut entries = Vec::new();
+        self.search_events(query, |batch| entries.extend(batch.iter().cloned()))?;
+        Ok(entries)
+    }
+
     /// Search events with streaming output. Calls `on_batch` with each page of results as they arrive.
-    /// Returns the total number of events retrieved.
-    pub fn search_events<F>(&self, query: &EventsQuery, mut on_batch: F) -> Result<usize, String>
+    /// Returns a `SearchSummary` with the number of events and pages fetched and whether
+    /// pagination stopped early (max pages, interrupt or deadline).
+    pub fn search_events<F>(
+        &self,
+        query: &EventsQuery,
+        on_batch: F,
+    ) -> Result<SearchSummary, DatadogError>
     where
         F: FnMut(&[EventEntry]),
     {
-        const MAX_PAGE_SIZE: u32 = 5000;
+        self.search_events_with_progress(query, on_batch, |_| {})
+    }
+
+    /// Like `search_events`, but also calls `on_page` with the running totals after each page.
+    pub fn search_events_with_progress<F, P>(
+        &self,
+        query: &EventsQuery,
+        mut on_batch: F,
+        mut on_page: P,
+    ) -> Result<SearchSummary, DatadogError>
+    where
+        F: FnMut(&[EventEntry]),
+        P: FnMut(&SearchSummary),
+    {
+        validate_time_range(&query.from, &query.to, chrono::Utc::now())
+            .map_err(DatadogError::Config)?;
 
         let mut total_count: usize = 0;
+        let mut pages: usize = 0;
         let mut cursor: Option<String> = None;
+        let mut truncated = false;
+        let mut interrupted = false;
+        let mut timed_out = false;
+        let mut seen_ids: HashSet<String> = HashSet::new();
+        // Shrinks whenever the API rejects a page as too large, and stays shrunk
+        let mut max_page_size = query.page_size.unwrap_or(MAX_PAGE_SIZE);
+
+        let stop_reason = loop {
+            // Stop between pages once interrupted, so the last batch is fully delivered
+            if self.is_interrupted() {
+                interrupted = true;
+                break "interrupted";
+            }
+            if self.is_past_deadline() {
+                timed_out = true;
+                break "deadline";
+            }
 
-        loop {
-            // Calculate page size: min(remaining, 5000)
-            let page_size = match query.limit {
-                Some(limit) => {
-                    let remaining = limit.saturating_sub(total_count as u32);
-                    remaining.min(MAX_PAGE_SIZE)
-                }
-                None => MAX_PAGE_SIZE,
-            };
+            let page_size = page_size(query.limit, total_count).min(max_page_size);
 
             // If we've already collected enough, stop
             if page_size == 0 {
-                break;
+                break "limit_reached";
             }
+            debug_pagination("request", || {
+                format!(
+                    "page_size={} max_page_size={} total_count={} cursor={}",
+                    page_size,
+                    max_page_size,
+                    total_count,
+                    cursor.is_some()
+                )
+            });
 
             let mut url = format!(
-                "https://api.datadoghq.com/api/v2/events?filter[query]={}&filter[from]={}&filter[to]={}&page[limit]={}",
-                urlencoding::encode(&query.query),
-                urlencoding::encode(&query.from),
-                urlencoding::encode(&query.to),
-                page_size
+                "{}?{}",
+                self.endpoint(EVENTS_SEARCH_PATH),
+                query.request_params(Some(page_size))
             );
 
             // Add cursor if we have one
@@ -122,82 +229,157 @@ impl DatadogClient {
                 url.push_str(&format!("&page[cursor]={}", urlencoding::encode(c)));
             }
 
-            let response = self
-                .client
-                .get(&url)
-                .header("DD-API-KEY", &self.api_key)
-                .header("DD-APPLICATION-KEY", &self.app_key)
-                .header("Content-Type", "application/json")
-                .send()
-                .map_err(|e| format!("Request failed: {}", e))?;
-
-            if !response.status().is_success() {
-                let status = response.status();
-                let body = response.text().unwrap_or_default();
-                return Err(format!("API error ({}): {}", status, body));
-            }
+            self.log_page(page_size, cursor.as_deref());
 
-            let internal_response: EventsSearchResponseInternal = response
-                .json()
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
+            let internal_response: EventsSearchResponseInternal =
+                match self.send_json("GET", &url, |client| client.get(&url)) {
+                    Err(e) if e.is_page_limit_error() && page_size > 1 => {
+                        max_page_size = self.reduce_page_size(page_size);
+                        continue;
+                    }
+                    result => result?,
+                };
+            pages += 1;
 
-            // Stream events from this page immediately
+            // Stream events from this page immediately, skipping any already seen
+            // (pages can overlap near cursor boundaries)
             if let Some(events) = internal_response.data {
+                let events = dedup_events(events, &mut seen_ids);
                 on_batch(&events);
                 total_count += events.len();
             }
 
+            on_page(&SearchSummary {
+                total: total_count,
+                pages,
+                ..Default::default()
+            });
+
             // Check for next page cursor
             let next_cursor = internal_response
                 .meta
                 .and_then(|m| m.page)
                 .and_then(|p| p.after);
+            debug_pagination("response", || {
+                format!(
+                    "page={} total_count={} next_cursor={}",
+                    pages,
+                    total_count,
+                    next_cursor.is_some()
+                )
+            });
 
             match next_cursor {
                 Some(c) => cursor = Some(c),
-                None => break, // No more pages
+                None => break "no_cursor", // No more pages
             }
 
             // Check if we've collected enough
             if let Some(limit) = query.limit
                 && total_count >= limit as usize
             {
-                break;
+                break "limit_reached";
+            }
+
+            if let Some(max_pages) = query.max_pages
+                && pages >= max_pages as usize
+            {
+                truncated = true;
+                break "max_pages";
             }
+        };
+        debug_pagination("stop", || {
+            format!(
+                "reason={} total_count={} pages={}",
+                stop_reason, total_count, pages
+            )
+        });
+
+        Ok(SearchSummary {
+            total: total_count,
+            pages,
+            truncated,
+            interrupted,
+            timed_out,
+            ..Default::default()
+        })
+    }
+}
+
+/// Minimal projection of an event for `--output summary`
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EventSummary<'a> {
+    pub ts: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub message: Option<&'a str>,
+}
+
+impl EventEntry {
+    pub fn summary(&self) -> EventSummary<'_> {
+        let inner = self.attributes.attributes.as_ref();
+        EventSummary {
+            ts: self.attributes.timestamp.as_deref(),
+            status: inner.and_then(|a| a.status.as_deref()),
+            title: self.title(),
+            message: self.attributes.message.as_deref(),
         }
+    }
 
-        Ok(total_count)
+    /// The event's title: the inner `title`, then `evt.name`, then a string `title` or `text`
+    /// key in the inner or outer flattened attributes
+    pub fn title(&self) -> Option<&str> {
+        let inner = self.attributes.attributes.as_ref();
+
+        inner
+            .and_then(|a| a.title.as_deref())
+            .or_else(|| {
+                inner
+                    .and_then(|a| a.evt.as_ref())
+                    .and_then(|e| e.name.as_deref())
+            })
+            .or_else(|| title_from_other(inner.and_then(|a| a.other.as_ref())))
+            .or_else(|| title_from_other(self.attributes.other.as_ref()))
     }
 }
 
-pub fn format_event_entry(entry: &EventEntry) -> String {
-    let timestamp = entry
-        .attributes
-        .timestamp
-        .as_ref()
-        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
-        .map(|dt| {
-            dt.with_timezone(&Utc)
-                .format("%Y-%m-%d %H:%M:%S")
-                .to_string()
-        })
-        .unwrap_or_else(|| "--------------------".to_string());
+/// Flattened attribute keys that hold an event's title when the structured fields don't
+const TITLE_KEYS: &[&str] = &["title", "text"];
 
-    // Try to get title from inner attributes, fall back to event name
-    let title = entry
-        .attributes
-        .attributes
-        .as_ref()
-        .and_then(|a| a.title.clone())
-        .or_else(|| {
-            entry
-                .attributes
-                .attributes
-                .as_ref()
-                .and_then(|a| a.evt.as_ref())
-                .and_then(|e| e.name.clone())
+/// The first non-empty string under one of `TITLE_KEYS`
+fn title_from_other(other: Option<&serde_json::Map<String, serde_json::Value>>) -> Option<&str> {
+    let other = other?;
+    TITLE_KEYS
+        .iter()
+        .find_map(|key| other.get(*key).and_then(|v| v.as_str()))
+        .filter(|title| !title.is_empty())
+}
+
+/// Drop events whose id is already in `seen`, recording new ids. Events without an id are kept.
+fn dedup_events(events: Vec<EventEntry>, seen: &mut HashSet<String>) -> Vec<EventEntry> {
+    events
+        .into_iter()
+        .filter(|event| match &event.id {
+            Some(id) => seen.insert(id.clone()),
+            None => true,
         })
-        .unwrap_or_else(|| "Untitled Event".to_string());
+        .collect()
+}
+
+pub fn format_event_entry(
+    entry: &EventEntry,
+    tz: DisplayTimezone,
+    time_format: TimeFormat,
+    highlight: Option<&Regex>,
+) -> String {
+    let timestamp = display_timestamp(
+        entry.attributes.timestamp.as_deref(),
+        tz,
+        time_format,
+        chrono::Utc::now(),
+    );
+
+    let title = entry.title().unwrap_or("Untitled Event").to_string();
 
     // Get status if available
     let status = entry
@@ -207,17 +389,19 @@ pub fn format_event_entry(entry: &EventEntry) -> String {
         .and_then(|a| a.status.clone())
         .unwrap_or_else(|| "info".to_string());
 
-    let status_colored = match status.to_lowercase().as_str() {
-        "error" => format!("{:5}", status.to_uppercase()).red().bold(),
-        "warning" | "warn" => format!("{:5}", status.to_uppercase()).yellow(),
-        "success" | "ok" => format!("{:5}", status.to_uppercase()).green(),
-        "info" => format!("{:5}", status.to_uppercase()).blue(),
-        _ => format!("{:5}", status.to_uppercase()).normal(),
-    };
+    let status_colored = color_status(&format!("{:5}", status.to_uppercase()));
 
     // Include message if available
     let message = entry.attributes.message.as_deref().unwrap_or("");
 
+    let (title, message) = match highlight {
+        Some(pattern) => (
+            highlight_matches(&title, pattern),
+            highlight_matches(message, pattern),
+        ),
+        None => (title, message.to_string()),
+    };
+
     if message.is_empty() {
         format!(
             "[{}] {} | {}",
@@ -235,3 +419,127 @@ pub fn format_event_entry(entry: &EventEntry) -> String {
         )
     }
 }
+
+/// Column names of the rows written by `format_event_csv`
+pub const EVENT_CSV_COLUMNS: [&str; 4] = ["timestamp", "status", "title", "message"];
+
+/// Render an event as one CSV record of `EVENT_CSV_COLUMNS`. Messages keep their newlines
+/// inside a quoted field.
+pub fn format_event_csv(entry: &EventEntry, tz: DisplayTimezone) -> String {
+    let summary = entry.summary();
+    let timestamp = summary
+        .ts
+        .map(|ts| format_timestamp(Some(ts), tz))
+        .unwrap_or_default();
+    csv_record(&[
+        &timestamp,
+        summary.status.unwrap_or(""),
+        summary.title.unwrap_or(""),
+        summary.message.unwrap_or(""),
+    ])
+}
+
+/// Render entries as an aligned table of timestamp, status, title and message
+pub fn format_event_table(entries: &[EventEntry], tz: DisplayTimezone) -> String {
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            let summary = entry.summary();
+            vec![
+                format_timestamp(summary.ts, tz),
+                summary.status.unwrap_or("info").to_uppercase(),
+                summary.title.unwrap_or("Untitled Event").to_string(),
+                summary.message.unwrap_or("").replace('\n', " "),
+            ]
+        })
+        .collect();
+
+    format_table(&["TIMESTAMP", "STATUS", "TITLE", "MESSAGE"], &rows, Some(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn event(id: Option<&str>) -> EventEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "type": "event",
+            "attributes": {}
+        }))
+        .unwrap()
+    }
+
+    fn ids(events: &[EventEntry]) -> Vec<Option<&str>> {
+        events.iter().map(|e| e.id.as_deref()).collect()
+    }
+
+    #[rstest]
+    #[case::ci(
+        serde_json::json!({ "attributes": { "title": "Pipeline failed", "evt": { "name": "ci" } } }),
+        Some("Pipeline failed")
+    )]
+    #[case::monitor(
+        serde_json::json!({ "attributes": { "evt": { "name": "[Triggered] High CPU" }, "status": "error" } }),
+        Some("[Triggered] High CPU")
+    )]
+    #[case::custom_inner_text(
+        serde_json::json!({ "attributes": { "text": "Deployed v1.2.3", "source": "my_app" } }),
+        Some("Deployed v1.2.3")
+    )]
+    #[case::custom_outer_title(
+        serde_json::json!({ "title": "Nightly backup", "message": "took 5m" }),
+        Some("Nightly backup")
+    )]
+    #[case::non_string_title(serde_json::json!({ "title": 42, "text": "" }), None)]
+    #[case::empty(serde_json::json!({}), None)]
+    fn test_event_title(#[case] attributes: serde_json::Value, #[case] expected: Option<&str>) {
+        let entry: EventEntry = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "type": "event",
+            "attributes": attributes
+        }))
+        .unwrap();
+
+        assert_eq!(entry.title(), expected);
+    }
+
+    #[test]
+    fn test_request_params_encode_query() {
+        let query = EventsQuery::new(
+            "source:github \"deploy failed\" &x=1".into(),
+            "now-1h".into(),
+            "now".into(),
+            Some(10),
+        )
+        .with_sort(Some("-timestamp".into()));
+
+        assert_eq!(
+            query.request_params(None),
+            "filter[query]=source%3Agithub%20%22deploy%20failed%22%20%26x%3D1&filter[from]=now-1h&filter[to]=now&page[limit]=10&sort=-timestamp"
+        );
+    }
+
+    #[test]
+    fn test_dedup_events_across_pages() {
+        let mut seen = HashSet::new();
+
+        let first = dedup_events(vec![event(Some("a")), event(Some("b"))], &mut seen);
+        let second = dedup_events(vec![event(Some("b")), event(Some("c"))], &mut seen);
+
+        assert_eq!(ids(&first), vec![Some("a"), Some("b")]);
+        assert_eq!(ids(&second), vec![Some("c")]);
+    }
+
+    #[test]
+    fn test_dedup_events_keeps_events_without_id() {
+        let mut seen = HashSet::new();
+
+        let first = dedup_events(vec![event(None), event(Some("a"))], &mut seen);
+        let second = dedup_events(vec![event(None), event(Some("a"))], &mut seen);
+
+        assert_eq!(ids(&first), vec![None, Some("a")]);
+        assert_eq!(ids(&second), vec![None]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+use crate::logs::LogEntry;
+
+/// What `--follow` has already printed, so each poll can re-fetch a lookback window for
+/// logs that were ingested late without printing any log twice.
+#[derive(Debug, Clone)]
+pub struct FollowWindow {
+    lookback: TimeDelta,
+    /// Newest log timestamp seen so far
+    latest: Option<DateTime<Utc>>,
+    /// Ids of logs already printed, with their timestamps so ids older than the window
+    /// can be forgotten
+    seen: HashMap<String, Option<DateTime<Utc>>>,
+}
+
+impl FollowWindow {
+    /// A window that rewinds each poll's start by `lookback`
+    pub fn new(lookback: Duration) -> Self {
+        Self {
+            lookback: TimeDelta::from_std(lookback).unwrap_or(TimeDelta::MAX),
+            latest: None,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// The logs of `batch` that haven't been seen before, remembering them. Logs without an
+    /// id can't be recognized again and are always kept.
+    pub fn fresh(&mut self, batch: &[LogEntry]) -> Vec<LogEntry> {
+        batch
+            .iter()
+            .filter(|entry| self.remember(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Record `entry`, returning whether it is new
+    fn remember(&mut self, entry: &LogEntry) -> bool {
+        let timestamp = entry
+            .attributes
+            .timestamp
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc));
+        self.latest = self.latest.max(timestamp);
+        match &entry.id {
+            Some(id) => self.seen.insert(id.clone(), timestamp).is_none(),
+            None => true,
+        }
+    }
+
+    /// The `from` time of the next poll: the newest timestamp seen, rewound by the lookback.
+    /// Timestamps ahead of `now` (clock skew between the log's source and this machine) are
+    /// capped at `now`, so the window never starts in the future. Ids from before the
+    /// window are forgotten, since it can't return them again. `None` until a log is seen.
+    pub fn next_from(&mut self, now: DateTime<Utc>) -> Option<String> {
+        let latest = self.latest?.min(now);
+        let from = latest
+            .checked_sub_signed(self.lookback)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        self.seen
+            .retain(|_, timestamp| timestamp.is_none_or(|ts| ts >= from));
+        Some(from.to_rfc3339_opts(SecondsFormat::Millis, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn log(id: Option<&str>, timestamp: &str) -> LogEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "attributes": { "timestamp": timestamp, "message": "hello" }
+        }))
+        .unwrap()
+    }
+
+    fn ids(entries: &[LogEntry]) -> Vec<Option<&str>> {
+        entries.iter().map(|e| e.id.as_deref()).collect()
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_refetched_logs_are_printed_once() {
+        let mut window = FollowWindow::new(Duration::from_secs(5));
+        let first = [
+            log(Some("a"), "2026-01-05T12:00:00Z"),
+            log(Some("b"), "2026-01-05T12:00:03Z"),
+        ];
+        // The lookback re-fetches b, and c arrived late with an older timestamp
+        let second = [
+            log(Some("c"), "2026-01-05T12:00:01Z"),
+            log(Some("b"), "2026-01-05T12:00:03Z"),
+            log(Some("d"), "2026-01-05T12:00:04Z"),
+            log(None, "2026-01-05T12:00:04Z"),
+        ];
+
+        assert_eq!(ids(&window.fresh(&first)), [Some("a"), Some("b")]);
+        assert_eq!(ids(&window.fresh(&second)), [Some("c"), Some("d"), None]);
+        assert!(window.fresh(&second[..3]).is_empty());
+    }
+
+    #[rstest]
+    #[case::rewinds_by_lookback("2026-01-05T12:01:00Z", "2026-01-05T12:00:05.000Z")]
+    #[case::caps_skewed_timestamps_at_now("2026-01-05T12:00:02Z", "2026-01-05T11:59:57.000Z")]
+    fn test_next_from(#[case] now: &str, #[case] expected: &str) {
+        let mut window = FollowWindow::new(Duration::from_secs(5));
+        assert_eq!(window.next_from(at(now)), None);
+
+        window.fresh(&[log(Some("a"), "2026-01-05T12:00:10Z")]);
+
+        assert_eq!(window.next_from(at(now)).as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn test_ids_before_the_window_are_forgotten() {
+        let mut window = FollowWindow::new(Duration::from_secs(5));
+        window.fresh(&[
+            log(Some("old"), "2026-01-05T12:00:00Z"),
+            log(Some("recent"), "2026-01-05T12:00:08Z"),
+            log(Some("new"), "2026-01-05T12:00:10Z"),
+        ]);
+
+        window.next_from(at("2026-01-05T12:01:00Z"));
+
+        let mut seen: Vec<&str> = window.seen.keys().map(String::as_str).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, ["new", "recent"]);
+    }
+}
@@ -0,0 +1,408 @@
+use colored::{ColoredString, Colorize};
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Color `text` by the status it contains, so logs, events and monitors share one palette.
+/// `text` may be padded. The color follows `status_severity`, so every status it recognizes
+/// is colored by its level.
+pub fn color_status(text: &str) -> ColoredString {
+    match status_severity(text) {
+        0..=3 => text.red().bold(),
+        4 => text.yellow(),
+        5 | 6 => text.green(),
+        7 => text.blue(),
+        8 => text.cyan(),
+        _ => text.normal(),
+    }
+}
+
+/// Sort key for a status, most severe first: syslog levels 0-7 in order, then TRACE, then
+/// anything unrecognized. Matching ignores surrounding whitespace and case.
+pub fn status_severity(status: &str) -> u8 {
+    match status.trim().to_uppercase().as_str() {
+        "EMERGENCY" | "EMERG" | "0" => 0,
+        "ALERT" | "1" => 1,
+        "CRITICAL" | "CRIT" | "2" => 2,
+        "ERROR" | "ERR" | "3" => 3,
+        "WARN" | "WARNING" | "4" => 4,
+        "NOTICE" | "5" => 5,
+        "INFO" | "OK" | "SUCCESS" | "6" => 6,
+        "DEBUG" | "7" => 7,
+        "TRACE" => 8,
+        _ => 9,
+    }
+}
+
+/// Keep only the dotted `paths` of `value`, e.g. `attributes.status`, nested as in the
+/// original. Numeric segments index into arrays. Missing or null paths (unset fields
+/// serialize as null) are left out, or kept as null with `keep_null`.
+pub fn project_json(
+    value: &serde_json::Value,
+    paths: &[String],
+    keep_null: bool,
+) -> serde_json::Value {
+    let mut projected = serde_json::Value::Object(serde_json::Map::new());
+
+    for path in paths {
+        let segments: Vec<&str> = path.split('.').collect();
+        let found = segments
+            .iter()
+            .try_fold(value, |value, segment| match value {
+                serde_json::Value::Object(map) => map.get(*segment),
+                serde_json::Value::Array(items) => {
+                    segment.parse::<usize>().ok().and_then(|i| items.get(i))
+                }
+                _ => None,
+            });
+        let leaf = match found {
+            Some(found) if !found.is_null() => found.clone(),
+            _ if keep_null => serde_json::Value::Null,
+            _ => continue,
+        };
+
+        // Rebuild the path as nested objects, keyed by segment even for array indexes
+        let (last, parents) = segments
+            .split_last()
+            .expect("split always yields a segment");
+        let mut target = &mut projected;
+        for segment in parents {
+            let serde_json::Value::Object(map) = target else {
+                break;
+            };
+            target = map
+                .entry(segment.to_string())
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        }
+        if let serde_json::Value::Object(map) = target {
+            map.insert(last.to_string(), leaf);
+        }
+    }
+
+    projected
+}
+
+/// Encode `fields` as one CSV record without the trailing newline, quoting fields that
+/// contain commas, quotes or newlines
+pub(crate) fn csv_record(fields: &[&str]) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing to a Vec can't fail
+    writer.write_record(fields).unwrap();
+    let bytes = writer.into_inner().unwrap();
+    let record = String::from_utf8(bytes).unwrap();
+    record
+        .strip_suffix('\n')
+        .map(str::to_string)
+        .unwrap_or(record)
+}
+
+/// Lay out `rows` under `header` as columns padded to their widest cell. The last column is
+/// left unpadded, and cells in `status_column` are colored with `color_status`.
+pub fn format_table(header: &[&str], rows: &[Vec<String>], status_column: Option<usize>) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.width()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let render = |cells: Vec<String>, is_header: bool| {
+        let last = cells.len().saturating_sub(1);
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                // Pad by display width, since `{:<w$}` counts chars
+                let padding = if i == last {
+                    0
+                } else {
+                    widths[i].saturating_sub(cell.width())
+                };
+                let padded = format!("{}{}", cell, " ".repeat(padding));
+                if is_header {
+                    padded.bold().to_string()
+                } else if Some(i) == status_column {
+                    color_status(&padded).to_string()
+                } else {
+                    padded
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    let mut lines = vec![render(header.iter().map(|h| h.to_string()).collect(), true)];
+    lines.extend(rows.iter().map(|row| render(row.clone(), false)));
+    lines.join("\n")
+}
+
+/// Kinds of token `colorize_json` colors differently
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JsonToken {
+    Key,
+    String,
+    Number,
+    /// `true`, `false` or `null`
+    Literal,
+    /// Punctuation and whitespace, left as is
+    Other,
+}
+
+/// Split serialized JSON into colorable tokens. Concatenating the pieces gives back `json`.
+fn json_tokens(json: &str) -> Vec<(JsonToken, &str)> {
+    let mut tokens = Vec::new();
+    let mut chars = json.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                JsonToken::String
+            }
+            '-' | '0'..='9' => {
+                while chars
+                    .next_if(|(_, c)| {
+                        c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')
+                    })
+                    .is_some()
+                {}
+                JsonToken::Number
+            }
+            'a'..='z' => {
+                while chars.next_if(|(_, c)| c.is_ascii_lowercase()).is_some() {}
+                JsonToken::Literal
+            }
+            _ => {
+                while chars
+                    .next_if(|(_, c)| !matches!(c, '"' | '-' | '0'..='9' | 'a'..='z'))
+                    .is_some()
+                {}
+                JsonToken::Other
+            }
+        };
+        let end = chars.peek().map_or(json.len(), |(i, _)| *i);
+        let text = &json[start..end];
+
+        // A string directly followed by ':' is an object key
+        let kind = match kind {
+            JsonToken::String if json[end..].trim_start().starts_with(':') => JsonToken::Key,
+            kind => kind,
+        };
+        tokens.push((kind, text));
+    }
+    tokens
+}
+
+/// Syntax-color serialized JSON: keys blue, strings green, numbers yellow and literals
+/// magenta. Returns `json` unchanged when colors are off (e.g. output is piped).
+pub fn colorize_json(json: &str) -> Cow<'_, str> {
+    if !colored::control::SHOULD_COLORIZE.should_colorize() {
+        return Cow::Borrowed(json);
+    }
+
+    let colored = json_tokens(json)
+        .into_iter()
+        .map(|(kind, text)| match kind {
+            JsonToken::Key => text.blue().to_string(),
+            JsonToken::String => text.green().to_string(),
+            JsonToken::Number => text.yellow().to_string(),
+            JsonToken::Literal => text.magenta().to_string(),
+            JsonToken::Other => text.to_string(),
+        })
+        .collect();
+    Cow::Owned(colored)
+}
+
+/// Shorten `text` to at most `max_width` terminal columns, ending it with an ellipsis when cut.
+/// Splits only between grapheme clusters, so combined and wide characters stay whole.
+pub fn truncate_to_width(text: &str, max_width: usize) -> Cow<'_, str> {
+    if text.width() <= max_width {
+        return Cow::Borrowed(text);
+    }
+    if max_width == 0 {
+        return Cow::Borrowed("");
+    }
+
+    // Leave one column for the ellipsis
+    let budget = max_width - 1;
+    let mut width = 0;
+    let mut truncated = String::new();
+    for grapheme in text.graphemes(true) {
+        width += grapheme.width();
+        if width > budget {
+            break;
+        }
+        truncated.push_str(grapheme);
+    }
+    truncated.push('…');
+    Cow::Owned(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use colored::{Color, Styles};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("ERROR", Some(Color::Red), true)]
+    #[case("critical", Some(Color::Red), true)]
+    #[case("EMERGENCY", Some(Color::Red), true)]
+    #[case("alert", Some(Color::Red), true)]
+    #[case("EMERG", Some(Color::Red), true)]
+    #[case("crit", Some(Color::Red), true)]
+    #[case("ERR", Some(Color::Red), true)]
+    #[case("WARN", Some(Color::Yellow), false)]
+    #[case("warning", Some(Color::Yellow), false)]
+    #[case("INFO ", Some(Color::Green), false)]
+    #[case("ok", Some(Color::Green), false)]
+    #[case("success", Some(Color::Green), false)]
+    #[case("DEBUG", Some(Color::Blue), false)]
+    #[case("trace", Some(Color::Cyan), false)]
+    #[case("-----", None, false)]
+    #[case("custom", None, false)]
+    #[case("Notice", Some(Color::Green), false)]
+    #[case("Error ", Some(Color::Red), true)]
+    #[case("WaRnInG", Some(Color::Yellow), false)]
+    #[case("0", Some(Color::Red), true)]
+    #[case("3    ", Some(Color::Red), true)]
+    #[case("4", Some(Color::Yellow), false)]
+    #[case("5", Some(Color::Green), false)]
+    #[case("6", Some(Color::Green), false)]
+    #[case("7", Some(Color::Blue), false)]
+    #[case("8", None, false)]
+    #[case("42", None, false)]
+    fn test_color_status(
+        #[case] status: &str,
+        #[case] expected_color: Option<Color>,
+        #[case] bold: bool,
+    ) {
+        let colored = color_status(status);
+
+        assert_eq!(colored.fgcolor, expected_color);
+        assert_eq!(colored.style.contains(Styles::Bold), bold);
+        assert_eq!(colored.input, status);
+    }
+
+    #[test]
+    fn test_status_severity_orders_most_severe_first() {
+        let mut statuses = vec!["info", "custom", "DEBUG", "Error", "4", "critical", "trace"];
+        statuses.sort_by_key(|s| status_severity(s));
+
+        assert_eq!(
+            statuses,
+            vec!["critical", "Error", "4", "info", "DEBUG", "trace", "custom"]
+        );
+    }
+
+    #[test]
+    fn test_format_table_aligns_by_display_width() {
+        colored::control::set_override(false);
+        let rows = vec![
+            vec!["ERROR".to_string(), "日本".to_string(), "first".to_string()],
+            vec![
+                "INFO".to_string(),
+                "api-server".to_string(),
+                "second".to_string(),
+            ],
+        ];
+
+        let table = format_table(&["STATUS", "SERVICE", "MESSAGE"], &rows, Some(0));
+
+        assert_eq!(
+            table,
+            "STATUS  SERVICE     MESSAGE\n\
+             ERROR   日本        first\n\
+             INFO    api-server  second"
+        );
+    }
+
+    #[test]
+    fn test_json_tokens() {
+        let json = r#"{"msg": "a \"b\": c", "n": -1.5e3, "ok": true, "x": null}"#;
+
+        let tokens = json_tokens(json);
+
+        use JsonToken::*;
+        assert_eq!(
+            tokens,
+            vec![
+                (Other, "{"),
+                (Key, r#""msg""#),
+                (Other, ": "),
+                (String, r#""a \"b\": c""#),
+                (Other, ", "),
+                (Key, r#""n""#),
+                (Other, ": "),
+                (Number, "-1.5e3"),
+                (Other, ", "),
+                (Key, r#""ok""#),
+                (Other, ": "),
+                (Literal, "true"),
+                (Other, ", "),
+                (Key, r#""x""#),
+                (Other, ": "),
+                (Literal, "null"),
+                (Other, "}"),
+            ]
+        );
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|(_, t)| *t)
+                .collect::<std::string::String>(),
+            json
+        );
+    }
+
+    #[rstest]
+    #[case("short", 10, "short")]
+    #[case("exactly10!", 10, "exactly10!")]
+    #[case("hello world", 8, "hello w…")]
+    #[case("日本語のログ", 7, "日本語…")]
+    #[case("e\u{301}e\u{301}e\u{301}", 2, "e\u{301}…")]
+    #[case("anything", 0, "")]
+    fn test_truncate_to_width(#[case] text: &str, #[case] width: usize, #[case] expected: &str) {
+        let truncated = truncate_to_width(text, width);
+
+        assert_eq!(truncated, expected);
+        assert!(truncated.width() <= width);
+    }
+
+    #[rstest]
+    #[case(&["attributes.status", "id"], false, r#"{"attributes":{"status":"error"},"id":"x"}"#)]
+    #[case(&["attributes.tags.1"], false, r#"{"attributes":{"tags":{"1":"b"}}}"#)]
+    #[case(&["attributes.missing", "id"], false, r#"{"id":"x"}"#)]
+    #[case(&["attributes.missing", "id"], true, r#"{"attributes":{"missing":null},"id":"x"}"#)]
+    #[case(&["id.nested"], false, r#"{}"#)]
+    #[case(&["attributes", "attributes.status"], false, r#"{"attributes":{"status":"error","tags":["a","b"]}}"#)]
+    fn test_project_json(#[case] paths: &[&str], #[case] keep_null: bool, #[case] expected: &str) {
+        let value = serde_json::json!({
+            "id": "x",
+            "attributes": { "status": "error", "tags": ["a", "b"] }
+        });
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+
+        assert_eq!(
+            project_json(&value, &paths, keep_null).to_string(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(&["a", "b"], "a,b")]
+    #[case(&["a,b", "c"], "\"a,b\",c")]
+    #[case(&["say \"hi\"", ""], "\"say \"\"hi\"\"\",")]
+    #[case(&["line\nbreak"], "\"line\nbreak\"")]
+    fn test_csv_record_quotes_fields(#[case] fields: &[&str], #[case] expected: &str) {
+        assert_eq!(csv_record(fields), expected);
+    }
+}
@@ -0,0 +1,243 @@
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::blocking::Request;
+use reqwest::header::HeaderMap;
+use serde_json::{Value, json};
+
+/// Headers whose values are always replaced in recorded entries
+const SECRET_HEADERS: &[&str] = &[
+    "DD-API-KEY",
+    "DD-APPLICATION-KEY",
+    "Authorization",
+    "Proxy-Authorization",
+    "Cookie",
+];
+
+/// Stand-in for redacted header values
+const REDACTED: &str = "***";
+
+/// Requests and responses recorded as an HTTP Archive (HAR 1.2), e.g. to share a failing
+/// session with Datadog support. Every attempt is recorded, so retries appear as
+/// separate entries.
+#[derive(Debug, Default)]
+pub struct HarRecorder {
+    entries: Mutex<Vec<Value>>,
+}
+
+/// How a recorded request ended
+pub(crate) enum HarOutcome<'a> {
+    Response {
+        status: reqwest::StatusCode,
+        headers: &'a HeaderMap,
+        /// Response body, when it was read before the response was handed back
+        body: Option<&'a str>,
+    },
+    /// The request failed without a response, e.g. a connection error
+    Failed(String),
+}
+
+impl HarRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a header's value is left out of recorded entries
+    pub(crate) fn is_secret_header(name: &str) -> bool {
+        SECRET_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name))
+    }
+
+    /// Record one request attempt that started at `started` and took `elapsed`. Values of
+    /// secret headers, and of headers for which `redact` returns true, are replaced.
+    pub(crate) fn record(
+        &self,
+        request: &Request,
+        redact: impl Fn(&str) -> bool,
+        started: DateTime<Utc>,
+        elapsed: Duration,
+        outcome: HarOutcome<'_>,
+    ) {
+        let redact = |name: &str| Self::is_secret_header(name) || redact(name);
+        let query_string: Vec<Value> = request
+            .url()
+            .query_pairs()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(String::from_utf8_lossy);
+
+        let mut har_request = json!({
+            "method": request.method().as_str(),
+            "url": request.url().as_str(),
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": header_list(request.headers(), redact),
+            "queryString": query_string,
+            "headersSize": -1,
+            "bodySize": body.as_ref().map_or(0, |body| body.len() as i64),
+        });
+        if let Some(body) = body {
+            har_request["postData"] = json!({ "mimeType": "application/json", "text": body });
+        }
+
+        let response = match outcome {
+            HarOutcome::Response {
+                status,
+                headers,
+                body,
+            } => {
+                let mut content = json!({
+                    "size": body.map_or(-1, |body| body.len() as i64),
+                    "mimeType": headers
+                        .get(reqwest::header::CONTENT_TYPE)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or(""),
+                });
+                if let Some(body) = body {
+                    content["text"] = json!(body);
+                }
+                json!({
+                    "status": status.as_u16(),
+                    "statusText": status.canonical_reason().unwrap_or(""),
+                    "httpVersion": "HTTP/1.1",
+                    "cookies": [],
+                    "headers": header_list(headers, redact),
+                    "content": content,
+                    "redirectURL": "",
+                    "headersSize": -1,
+                    "bodySize": -1,
+                })
+            }
+            // HAR has no failed-request entry; status 0 is what browsers record
+            HarOutcome::Failed(error) => json!({
+                "status": 0,
+                "statusText": "",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": [],
+                "content": { "size": 0, "mimeType": "" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+                "_error": error,
+            }),
+        };
+
+        let time = elapsed.as_secs_f64() * 1000.0;
+        self.entries.lock().unwrap().push(json!({
+            "startedDateTime": started.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "time": time,
+            "request": har_request,
+            "response": response,
+            "cache": {},
+            "timings": { "send": 0, "wait": time, "receive": 0 },
+        }));
+    }
+
+    /// The recorded entries as a HAR document
+    pub fn to_json(&self) -> Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "datadog-cli", "version": env!("CARGO_PKG_VERSION") },
+                "entries": *self.entries.lock().unwrap(),
+            }
+        })
+    }
+
+    /// Write the recorded entries to `path` as a HAR file
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.to_json()).map_err(io::Error::other)?;
+        std::fs::write(path, contents)
+    }
+}
+
+fn header_list(headers: &HeaderMap, redact: impl Fn(&str) -> bool) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if redact(name.as_str()) {
+                REDACTED.into()
+            } else {
+                String::from_utf8_lossy(value.as_bytes())
+            };
+            json!({ "name": name.as_str(), "value": value })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> Request {
+        reqwest::blocking::Client::new()
+            .post("https://api.datadoghq.com/api/v2/logs/events/search?x=1")
+            .header("DD-API-KEY", "secret")
+            .header("X-Proxy-Token", "also-secret")
+            .header("X-Trace", "visible")
+            .body(r#"{"filter":{}}"#)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record_redacts_secrets() {
+        let har = HarRecorder::new();
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert("content-type", "application/json".parse().unwrap());
+
+        har.record(
+            &request(),
+            |name| name.eq_ignore_ascii_case("x-proxy-token"),
+            Utc::now(),
+            Duration::from_millis(25),
+            HarOutcome::Response {
+                status: reqwest::StatusCode::BAD_REQUEST,
+                headers: &response_headers,
+                body: Some(r#"{"errors":["bad"]}"#),
+            },
+        );
+
+        let log = har.to_json();
+        let entry = &log["log"]["entries"][0];
+        let headers = entry["request"]["headers"].to_string();
+        assert!(!headers.contains("secret"), "{}", headers);
+        assert!(headers.contains("visible"));
+        assert_eq!(entry["request"]["method"], "POST");
+        assert_eq!(entry["request"]["queryString"][0]["name"], "x");
+        assert_eq!(entry["request"]["postData"]["text"], r#"{"filter":{}}"#);
+        assert_eq!(entry["response"]["status"], 400);
+        assert_eq!(
+            entry["response"]["content"]["text"],
+            r#"{"errors":["bad"]}"#
+        );
+        assert_eq!(entry["time"], 25.0);
+    }
+
+    #[test]
+    fn test_failed_requests_are_recorded_with_status_zero() {
+        let har = HarRecorder::new();
+
+        har.record(
+            &request(),
+            |_| false,
+            Utc::now(),
+            Duration::ZERO,
+            HarOutcome::Failed("connection refused".into()),
+        );
+
+        let log = har.to_json();
+        assert_eq!(log["log"]["version"], "1.2");
+        assert_eq!(log["log"]["entries"][0]["response"]["status"], 0);
+        assert_eq!(
+            log["log"]["entries"][0]["response"]["_error"],
+            "connection refused"
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use colored::Colorize;
+use regex::Regex;
+
+/// Wrap every match of `pattern` in `text` with a highlight color
+pub fn highlight_matches(text: &str, pattern: &Regex) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for m in pattern.find_iter(text) {
+        if m.is_empty() {
+            continue;
+        }
+        out.push_str(&text[last..m.start()]);
+        out.push_str(&m.as_str().bright_yellow().underline().to_string());
+        last = m.end();
+    }
+
+    out.push_str(&text[last..]);
+    out
+}
@@ -1,7 +1,49 @@
+pub mod aggregate;
+pub mod auth;
+pub mod cache;
+pub mod error;
 pub mod events;
+pub mod follow;
+pub mod format;
+pub mod har;
+pub mod highlight;
 pub mod logs;
+pub mod metrics;
+pub mod monitors;
+pub mod sample;
+pub mod template;
+pub mod throttle;
+pub mod time;
+pub mod traces;
 pub mod url;
 
-pub use events::{EventEntry, EventsQuery, format_event_entry};
-pub use logs::{DatadogClient, LogEntry, LogsQuery, format_log_entry};
-pub use url::{DatadogResource, parse_datadog_url};
+pub use aggregate::{
+    LogsAggregateBucket, LogsAggregateQuery, StatusCounts, format_aggregate_table,
+    format_status_table, sort_status_buckets,
+};
+pub use cache::ResultCache;
+pub use error::DatadogError;
+pub use events::{
+    EVENT_CSV_COLUMNS, EventEntry, EventSummary, EventsQuery, format_event_csv, format_event_entry,
+    format_event_table,
+};
+pub use follow::FollowWindow;
+pub use format::{color_status, colorize_json, project_json, status_severity};
+pub use har::HarRecorder;
+pub use logs::{
+    BatchResult, DEBUG_ENV_VAR, DEFAULT_USER_AGENT, DatadogClient, DatadogClientBuilder,
+    EVENTS_SEARCH_PATH, LOG_CSV_COLUMNS, LOGS_SEARCH_PATH, LogAttributes, LogEntry,
+    LogFormatOptions, LogSummary, LogsIter, LogsQuery, MAX_PAGE_SIZE, STORAGE_TIERS, SearchSummary,
+    and_any_of, and_none_of, api_base_url, collect_facets, escape_query_value, format_log_csv,
+    format_log_entry, format_log_table, looks_like_api_key, resolve_site, site_from_env,
+};
+pub use metrics::{MetricSeries, MetricsQuery, MetricsResponse, format_metric_series};
+pub use monitors::{Monitor, MonitorsQuery, format_monitor};
+pub use sample::Reservoir;
+pub use template::LogTemplate;
+pub use throttle::RateLimiter;
+pub use time::{
+    DisplayTimezone, TimeFormat, parse_duration, parse_time, resolve_time, validate_time_range,
+};
+pub use traces::TracesQuery;
+pub use url::{DatadogResource, ResourceKind, parse_datadog_url};
@@ -1,7 +1,25 @@
-use chrono::{DateTime, Utc};
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
+use regex::Regex;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::DatadogError;
+use crate::format::{color_status, csv_record, format_table, truncate_to_width};
+use crate::har::{HarOutcome, HarRecorder};
+use crate::highlight::highlight_matches;
+use crate::template::lookup_field;
+use crate::throttle::RateLimiter;
+use crate::time::{
+    DisplayTimezone, TimeFormat, display_timestamp, format_timestamp, parse_time,
+    validate_time_range,
+};
 
 /// Parameters for a logs search query
 #[derive(Debug, Clone)]
@@ -11,8 +29,33 @@ pub struct LogsQuery {
     pub to: String,
     /// Maximum number of logs to retrieve. None = fetch all.
     pub limit: Option<u32>,
+    /// Log indexes to search. Empty = the account's default indexes.
+    pub indexes: Vec<String>,
+    /// Storage tier to search (one of `STORAGE_TIERS`). None = indexed logs.
+    pub storage_tier: Option<String>,
+    /// Sort field, prefixed with `-` for descending, e.g. `-@duration`. None = `timestamp`.
+    pub sort: Option<String>,
+    /// Stop after this many pages even if more results remain. None = no cap.
+    pub max_pages: Option<u32>,
+    /// Saved view ID from an explorer URL. Carried as metadata only; it is not resolved.
+    pub saved_view: Option<String>,
+    /// Page cursor to start from, e.g. `SearchSummary::cursor` of an interrupted search
+    pub cursor: Option<String>,
+    /// Largest page to request. None = `MAX_PAGE_SIZE`, halved while the API rejects it.
+    pub page_size: Option<u32>,
+    /// Timezone sent as `options.timezone`, e.g. `America/New_York`. None = UTC.
+    pub timezone: Option<String>,
 }
 
+/// Path of the logs search endpoint, relative to the API base URL
+pub const LOGS_SEARCH_PATH: &str = "/api/v2/logs/events/search";
+
+/// Path of the events search endpoint, relative to the API base URL
+pub const EVENTS_SEARCH_PATH: &str = "/api/v2/events";
+
+/// Storage tiers accepted by the logs search API
+pub const STORAGE_TIERS: &[&str] = &["indexes", "online-archives", "flex"];
+
 impl LogsQuery {
     pub fn new(query: String, from: String, to: String, limit: Option<u32>) -> Self {
         Self {
@@ -20,7 +63,212 @@ impl LogsQuery {
             from,
             to,
             limit,
+            indexes: Vec::new(),
+            storage_tier: None,
+            sort: None,
+            max_pages: None,
+            saved_view: None,
+            cursor: None,
+            page_size: None,
+            timezone: None,
+        }
+    }
+
+    /// Request at most `page_size` entries per page, for accounts capped below `MAX_PAGE_SIZE`
+    pub fn with_page_size(mut self, page_size: Option<u32>) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Have the API interpret the search in `timezone` (see `DisplayTimezone::api_timezone`)
+    pub fn with_timezone(mut self, timezone: Option<String>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Resume pagination from `cursor` instead of the first page
+    pub fn with_cursor(mut self, cursor: Option<String>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// AND a `facet:value` term into the query, quoting the value if it has special characters
+    pub fn with_term(mut self, facet: &str, value: &str) -> Self {
+        self.query = and_any_of(&self.query, facet, &[value]);
+        self
+    }
+
+    /// Record the saved view this query came from
+    pub fn with_saved_view(mut self, saved_view: Option<String>) -> Self {
+        self.saved_view = saved_view;
+        self
+    }
+
+    /// Cap the number of pages requested, independently of `limit`
+    pub fn with_max_pages(mut self, max_pages: Option<u32>) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Sort by a field other than `timestamp`; prefix it with `-` for descending order
+    pub fn with_sort(mut self, sort: Option<String>) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Search a specific storage tier, e.g. `flex` or `online-archives`
+    pub fn with_storage_tier(mut self, storage_tier: Option<String>) -> Self {
+        self.storage_tier = storage_tier;
+        self
+    }
+
+    /// Restrict the search to the given log indexes
+    pub fn with_indexes(mut self, indexes: Vec<String>) -> Self {
+        self.indexes = indexes;
+        self
+    }
+
+    /// Number of logs requested in the first page
+    pub fn first_page_size(&self) -> u32 {
+        page_size(self.limit, 0).min(self.page_size.unwrap_or(MAX_PAGE_SIZE))
+    }
+
+    /// JSON body of the first search request, as sent to `LOGS_SEARCH_PATH`
+    pub fn request_body(&self) -> serde_json::Value {
+        serde_json::to_value(self.search_request(self.first_page_size(), self.cursor.clone()))
+            .expect("search request is always serializable")
+    }
+
+    fn search_request(&self, page_size: u32, cursor: Option<String>) -> LogsSearchRequest {
+        LogsSearchRequest {
+            filter: LogsFilter {
+                query: self.query.clone(),
+                from: self.from.clone(),
+                to: self.to.clone(),
+                indexes: self.indexes.clone(),
+                storage_tier: self.storage_tier.clone(),
+            },
+            page: PageOptions {
+                limit: page_size,
+                cursor,
+            },
+            sort: self.sort.clone().unwrap_or_else(|| "timestamp".to_string()),
+            options: self
+                .timezone
+                .clone()
+                .map(|timezone| SearchOptions { timezone }),
+        }
+    }
+}
+
+/// Keys of a `LogsQuery` spec, in the order `Display` writes them
+const QUERY_SPEC_KEYS: [&str; 6] = ["query", "from", "to", "limit", "sort", "indexes"];
+
+/// Parse a compact spec such as `query=service:api;from=now-1h;to=now;limit=500`.
+///
+/// Keys are `query` (required), `from` and `to` (default `now-15m` and `now`, in any form
+/// `parse_time` accepts), `limit` (omitted = no limit), `sort` and `indexes`
+/// (comma-separated). A `;` or `\` inside a value is escaped with a backslash.
+impl std::str::FromStr for LogsQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields: BTreeMap<&str, String> = BTreeMap::new();
+        for segment in split_spec(s)? {
+            if segment.trim().is_empty() {
+                continue;
+            }
+            let (key, value) = segment.split_once('=').ok_or_else(|| {
+                format!(
+                    "Invalid query spec segment: {} (expected key=value)",
+                    segment
+                )
+            })?;
+            let key = QUERY_SPEC_KEYS
+                .into_iter()
+                .find(|known| *known == key.trim())
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown query spec key: {} (expected {})",
+                        key.trim(),
+                        QUERY_SPEC_KEYS.join(", ")
+                    )
+                })?;
+            if fields.insert(key, value.to_string()).is_some() {
+                return Err(format!("Duplicate query spec key: {}", key));
+            }
+        }
+
+        let query = fields
+            .remove("query")
+            .ok_or("Query spec is missing query=")?;
+        let from = parse_time(fields.get("from").map_or("now-15m", String::as_str))?;
+        let to = parse_time(fields.get("to").map_or("now", String::as_str))?;
+        let limit = fields
+            .get("limit")
+            .map(|limit| {
+                limit
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid limit in query spec: {}", limit))
+            })
+            .transpose()?;
+        let indexes = fields
+            .get("indexes")
+            .map(|indexes| {
+                indexes
+                    .split(',')
+                    .map(|index| index.trim().to_string())
+                    .filter(|index| !index.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(LogsQuery::new(query, from, to, limit)
+            .with_sort(fields.remove("sort"))
+            .with_indexes(indexes))
+    }
+}
+
+/// Split a query spec on unescaped `;`, unescaping `\;` and `\\`
+fn split_spec(spec: &str) -> Result<Vec<String>, String> {
+    let mut segments = vec![String::new()];
+    let mut chars = spec.chars();
+    while let Some(c) = chars.next() {
+        let segment = segments.last_mut().expect("segments is never empty");
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ (';' | '\\')) => segment.push(escaped),
+                _ => return Err(format!("Invalid escape in query spec: {}", spec)),
+            },
+            ';' => segments.push(String::new()),
+            c => segment.push(c),
+        }
+    }
+    Ok(segments)
+}
+
+/// The compact spec `FromStr` parses, with fields in a fixed order
+impl fmt::Display for LogsQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let escape = |value: &str| value.replace('\\', "\\\\").replace(';', "\\;");
+        write!(
+            f,
+            "query={};from={};to={}",
+            escape(&self.query),
+            escape(&self.from),
+            escape(&self.to)
+        )?;
+        if let Some(limit) = self.limit {
+            write!(f, ";limit={}", limit)?;
+        }
+        if let Some(sort) = &self.sort {
+            write!(f, ";sort={}", escape(sort))?;
         }
+        if !self.indexes.is_empty() {
+            write!(f, ";indexes={}", escape(&self.indexes.join(",")))?;
+        }
+        Ok(())
     }
 }
 
@@ -30,6 +278,13 @@ struct LogsSearchRequest {
     filter: LogsFilter,
     page: PageOptions,
     sort: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<SearchOptions>,
+}
+
+#[derive(Serialize)]
+struct SearchOptions {
+    timezone: String,
 }
 
 #[derive(Serialize)]
@@ -37,6 +292,10 @@ struct LogsFilter {
     query: String,
     from: String,
     to: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    indexes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_tier: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -69,7 +328,7 @@ pub struct LogsSearchResponse {
     pub data: Option<Vec<LogEntry>>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct LogEntry {
     pub id: Option<String>,
     #[serde(rename = "type")]
@@ -77,7 +336,7 @@ pub struct LogEntry {
     pub attributes: LogAttributes,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct LogAttributes {
     pub timestamp: Option<String>,
     pub status: Option<String>,
@@ -89,126 +348,1250 @@ pub struct LogAttributes {
     pub attributes: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
+impl LogAttributes {
+    /// Look up a flattened attribute by key. Custom attributes that Datadog nests
+    /// under `attributes` are checked when the key isn't found at the top level.
+    pub fn get_attribute(&self, key: &str) -> Option<&serde_json::Value> {
+        let map = self.attributes.as_ref()?;
+
+        map.get(key).or_else(|| {
+            map.get("attributes")
+                .and_then(|nested| nested.as_object())
+                .and_then(|nested| nested.get(key))
+        })
+    }
+
+    /// Look up an attribute by dotted path such as `http.status_code`. A key containing
+    /// the dots literally wins; otherwise each segment descends into a nested object.
+    pub fn get_path(&self, path: &str) -> Option<&serde_json::Value> {
+        if let Some(value) = self.get_attribute(path) {
+            return Some(value);
+        }
+
+        let mut segments = path.split('.');
+        let first = self.get_attribute(segments.next()?)?;
+        segments.try_fold(first, |value, segment| value.as_object()?.get(segment))
+    }
+
+    /// Dotted paths of every attribute with a value, e.g. `http.status_code`. Custom
+    /// attributes nested under `attributes` are listed by their path within it.
+    pub fn attribute_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        if let Some(map) = &self.attributes {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("attributes", serde_json::Value::Object(nested)) => {
+                        collect_keys(nested, "", &mut keys)
+                    }
+                    _ => collect_key(key, value, &mut keys),
+                }
+            }
+        }
+        keys
+    }
+
+    /// `http.method`, e.g. "GET"
+    pub fn http_method(&self) -> Option<&str> {
+        self.get_path("http.method")?.as_str()
+    }
+
+    /// `http.status_code`, accepting numbers or numeric strings
+    pub fn http_status(&self) -> Option<u16> {
+        coerce_u64(self.get_path("http.status_code")?).and_then(|n| u16::try_from(n).ok())
+    }
+
+    /// `duration` in nanoseconds (Datadog's standard unit), accepting numbers or numeric strings
+    pub fn duration_ns(&self) -> Option<u64> {
+        coerce_u64(self.get_path("duration")?)
+    }
+
+    /// APM trace ID from `dd.trace_id` or `trace_id`, as a string whatever its JSON type
+    pub fn trace_id(&self) -> Option<String> {
+        match self
+            .get_path("dd.trace_id")
+            .or_else(|| self.get_path("trace_id"))?
+        {
+            serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    /// All custom attributes sorted by key: unknown top-level fields plus the
+    /// contents of the nested `attributes` object.
+    pub fn custom_attributes(&self) -> BTreeMap<&str, &serde_json::Value> {
+        let mut custom = BTreeMap::new();
+        let Some(map) = self.attributes.as_ref() else {
+            return custom;
+        };
+
+        for (key, value) in map {
+            match (key.as_str(), value) {
+                ("attributes", serde_json::Value::Object(nested)) => {
+                    custom.extend(nested.iter().map(|(k, v)| (k.as_str(), v)));
+                }
+                _ => {
+                    custom.insert(key.as_str(), value);
+                }
+            }
+        }
+        custom
+    }
+}
+
+/// Read a non-negative integer from a JSON number or numeric string. Fractions are truncated.
+fn coerce_u64(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(n) => n
+            .as_u64()
+            .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)),
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            s.parse::<u64>().ok().or_else(|| {
+                s.parse::<f64>()
+                    .ok()
+                    .filter(|f| *f >= 0.0)
+                    .map(|f| f as u64)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Minimal projection of a log entry for `--output summary`
+#[derive(Serialize, Debug, PartialEq)]
+pub struct LogSummary<'a> {
+    pub ts: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub service: Option<&'a str>,
+    pub message: Option<&'a str>,
+}
+
+impl LogEntry {
+    pub fn summary(&self) -> LogSummary<'_> {
+        LogSummary {
+            ts: self.attributes.timestamp.as_deref(),
+            status: self.attributes.status.as_deref(),
+            service: self.attributes.service.as_deref(),
+            message: self.attributes.message.as_deref(),
+        }
+    }
+}
+
+/// AND `facet:(a OR b ...)` into `query`, quoting values that have special characters.
+/// A single value becomes a plain `facet:value` term; no values leave `query` unchanged.
+pub fn and_any_of(query: &str, facet: &str, values: &[&str]) -> String {
+    match any_of_term(facet, values) {
+        Some(term) => and_term(query, &term),
+        None => query.to_string(),
+    }
+}
+
+/// AND `-facet:(a OR b ...)` into `query`, excluding every value. Quoting and the
+/// single-value form follow `and_any_of`; no values leave `query` unchanged.
+pub fn and_none_of(query: &str, facet: &str, values: &[&str]) -> String {
+    match any_of_term(facet, values) {
+        Some(term) => and_term(query, &format!("-{}", term)),
+        None => query.to_string(),
+    }
+}
+
+/// `facet:value` or `facet:(a OR b ...)`, or None without values
+fn any_of_term(facet: &str, values: &[&str]) -> Option<String> {
+    let values: Vec<String> = values.iter().map(|v| escape_query_value(v)).collect();
+    match values.as_slice() {
+        [] => None,
+        [value] => Some(format!("{}:{}", facet, value)),
+        values => Some(format!("{}:({})", facet, values.join(" OR "))),
+    }
+}
+
+/// AND `term` into `query`
+fn and_term(query: &str, term: &str) -> String {
+    match query.trim() {
+        "" | "*" => term.to_string(),
+        // Keep a top-level OR from swallowing the new term
+        query if has_top_level_or(query) => format!("({}) {}", query, term),
+        query => format!("{} {}", query, term),
+    }
+}
+
+/// Whether `query` has an `OR` outside any parentheses or quoted value
+fn has_top_level_or(query: &str) -> bool {
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut top_level = String::new();
+
+    for c in query.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth = depth.saturating_sub(1),
+            _ if !quoted && depth == 0 => {
+                top_level.push(c);
+                continue;
+            }
+            _ => {}
+        }
+        // Anything not at the top level separates words
+        top_level.push(' ');
+    }
+
+    top_level.split_whitespace().any(|word| word == "OR")
+}
+
+/// Push the dotted path of every leaf under `map`, prefixed with `prefix`
+fn collect_keys(
+    map: &serde_json::Map<String, serde_json::Value>,
+    prefix: &str,
+    keys: &mut Vec<String>,
+) {
+    for (key, value) in map {
+        collect_key(&format!("{}{}", prefix, key), value, keys);
+    }
+}
+
+/// Push `path`, or the paths below it if `value` is a non-empty object
+fn collect_key(path: &str, value: &serde_json::Value, keys: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(nested) if !nested.is_empty() => {
+            collect_keys(nested, &format!("{}.", path), keys)
+        }
+        _ => keys.push(path.to_string()),
+    }
+}
+
+/// The sorted, deduplicated attribute paths seen across `entries`
+pub fn collect_facets(entries: &[LogEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .flat_map(|entry| entry.attributes.attribute_keys())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Quote a value for use in a Datadog query unless it is a plain word, escaping any
+/// backslashes and quotes inside it. Use this when building `facet:value` terms from
+/// user input, e.g. `format!("service:{}", escape_query_value(name))`.
+pub fn escape_query_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// One query's entries and search summary from `DatadogClient::search_logs_batch`
+pub type BatchResult = Result<(Vec<LogEntry>, SearchSummary), DatadogError>;
+
+/// Longest wait between retries
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Delay before retry number `attempt` (1-based): doubling from 500ms, capped at `MAX_BACKOFF`
+fn backoff_delay(attempt: u32) -> Duration {
+    let factor = 1u64
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    Duration::from_millis(500u64.saturating_mul(factor)).min(MAX_BACKOFF)
+}
+
+/// Maximum number of entries the search APIs return per page
+pub const MAX_PAGE_SIZE: u32 = 5000;
+
+/// Page size for the next request: min(remaining, MAX_PAGE_SIZE), or 0 once `limit` is reached
+pub(crate) fn page_size(limit: Option<u32>, fetched: usize) -> u32 {
+    match limit {
+        Some(limit) => {
+            let remaining = limit.saturating_sub(fetched.min(u32::MAX as usize) as u32);
+            remaining.min(MAX_PAGE_SIZE)
+        }
+        None => MAX_PAGE_SIZE,
+    }
+}
+
+/// Env var that prints each pagination decision to stderr when set, e.g. `DATADOG_CLI_DEBUG=1`
+pub const DEBUG_ENV_VAR: &str = "DATADOG_CLI_DEBUG";
+
+static PAGINATION_DEBUG: LazyLock<bool> =
+    LazyLock::new(|| debug_enabled(env::var(DEBUG_ENV_VAR).ok().as_deref()));
+
+/// Whether a DATADOG_CLI_DEBUG value turns debugging on: anything but unset, empty or "0"
+fn debug_enabled(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Print a pagination decision as `[pagination] <event> key=value ...` when DATADOG_CLI_DEBUG
+/// is set. `fields` is only called then, so this costs a flag check otherwise.
+pub(crate) fn debug_pagination(event: &str, fields: impl FnOnce() -> String) {
+    if *PAGINATION_DEBUG {
+        eprintln!("[pagination] {} {}", event, fields());
+    }
+}
+
+/// Summary of a completed paginated search
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchSummary {
+    /// Total number of entries retrieved
+    pub total: usize,
+    /// Number of pages requested from the API
+    pub pages: usize,
+    /// Pagination stopped at `max_pages` while more results were available
+    pub truncated: bool,
+    /// Pagination stopped early because the client's interrupt flag was set
+    pub interrupted: bool,
+    /// Pagination stopped early because the client's deadline passed
+    pub timed_out: bool,
+    /// Cursor for the next page when more results remain. Logs searches only.
+    pub cursor: Option<String>,
+}
+
+/// User-Agent sent when none is configured, e.g. `datadog-cli/0.2.0`
+pub const DEFAULT_USER_AGENT: &str = concat!("datadog-cli/", env!("CARGO_PKG_VERSION"));
+
+/// Site used when none is configured
+pub const DEFAULT_SITE: &str = "datadoghq.com";
+
+/// Short names accepted in place of a site domain
+const SITE_ALIASES: &[(&str, &str)] = &[
+    ("us1", "datadoghq.com"),
+    ("us3", "us3.datadoghq.com"),
+    ("us5", "us5.datadoghq.com"),
+    ("eu", "datadoghq.eu"),
+    ("eu1", "datadoghq.eu"),
+    ("ap1", "ap1.datadoghq.com"),
+    ("ap2", "ap2.datadoghq.com"),
+    ("gov", "ddog-gov.com"),
+    ("us1-fed", "ddog-gov.com"),
+];
+
+/// Canonical site domain for a site or alias, e.g. `eu` or `https://datadoghq.eu/` become
+/// `datadoghq.eu`. Unknown domains are passed through.
+pub fn resolve_site(site: &str) -> String {
+    let site = site.trim().to_lowercase();
+    let site = site
+        .strip_prefix("https://")
+        .unwrap_or(&site)
+        .trim_end_matches('/');
+
+    SITE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == site)
+        .map(|(_, domain)| domain.to_string())
+        .unwrap_or_else(|| site.to_string())
+}
+
+/// The known Datadog site a web app host belongs to, e.g. `datadoghq.eu` for
+/// `app.datadoghq.eu` or `us3.datadoghq.com` for `us3.datadoghq.com`. The most specific
+/// site wins; hosts outside every known site return None.
+pub(crate) fn site_for_host(host: &str) -> Option<&'static str> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    SITE_ALIASES
+        .iter()
+        .map(|(_, site)| *site)
+        .filter(|site| {
+            host == *site
+                || host
+                    .strip_suffix(site)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+        .max_by_key(|site| site.len())
+}
+
+/// The site from DD_SITE, or `DEFAULT_SITE`
+pub fn site_from_env() -> String {
+    env::var("DD_SITE")
+        .map(|site| resolve_site(&site))
+        .unwrap_or_else(|_| DEFAULT_SITE.to_string())
+}
+
+/// API base URL for a Datadog site, e.g. `https://api.datadoghq.eu`
+pub fn api_base_url(site: &str) -> String {
+    format!("https://api.{}", site)
+}
+
+/// Cloning is cheap: clones share the underlying connection pool
+#[derive(Clone)]
 pub struct DatadogClient {
     pub(crate) api_key: String,
     pub(crate) app_key: String,
     pub(crate) client: reqwest::blocking::Client,
+    /// Datadog site domain, e.g. `datadoghq.com` or `datadoghq.eu`
+    pub(crate) site: String,
+    /// API root that request paths are appended to, e.g. `https://api.datadoghq.com`
+    pub(crate) base_url: String,
+    /// How many times to retry a request after a connection error, 429 or 5xx response
+    pub(crate) retries: u32,
+    /// Extra headers sent with every request. These replace default headers with the same name.
+    pub(crate) headers: HeaderMap,
+    /// When set, paginated searches stop before requesting the next page
+    pub(crate) interrupt: Option<Arc<AtomicBool>>,
+    /// Searches stop requesting pages once this instant has passed
+    pub(crate) deadline: Option<Instant>,
+    /// Verbosity of request logging to stderr (0 = off, 1 = requests, 2 = also error bodies)
+    pub(crate) verbosity: u8,
+    /// Records every request attempt for a HAR file
+    pub(crate) har: Option<Arc<HarRecorder>>,
+    /// Throttles every request attempt, including retries
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
 }
 
-impl DatadogClient {
-    pub fn new() -> Result<Self, String> {
-        let api_key = env::var("DD_API_KEY")
-            .map_err(|_| "Missing environment variable: DD_API_KEY".to_string())?;
-        let app_key = env::var("DD_APP_KEY")
-            .map_err(|_| "Missing environment variable: DD_APP_KEY".to_string())?;
+/// Stand-in for secrets in `Debug` output
+const REDACTED: &str = "***";
+
+/// Keys and header values are redacted, since they may hold credentials
+impl fmt::Debug for DatadogClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatadogClient")
+            .field("api_key", &REDACTED)
+            .field("app_key", &REDACTED)
+            .field("site", &self.site)
+            .field("base_url", &self.base_url)
+            .field("retries", &self.retries)
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .field("interrupt", &self.interrupt)
+            .field("deadline", &self.deadline)
+            .field("verbosity", &self.verbosity)
+            .field("har", &self.har.is_some())
+            .field("rate_limiter", &self.rate_limiter)
+            .finish()
+    }
+}
+
+/// Whether `key` has the shape of a Datadog API key: 32 hex characters
+pub fn looks_like_api_key(key: &str) -> bool {
+    is_hex_of_len(key.trim(), 32)
+}
+
+/// Whether `key` has the shape of a Datadog application key: 40 hex characters
+fn looks_like_app_key(key: &str) -> bool {
+    is_hex_of_len(key.trim(), 40)
+}
+
+fn is_hex_of_len(value: &str, len: usize) -> bool {
+    value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether each key has the shape of the other kind
+fn keys_look_swapped(api_key: &str, app_key: &str) -> bool {
+    looks_like_app_key(api_key) && looks_like_api_key(app_key)
+}
+
+/// Builder for `DatadogClient`, for callers that don't want to configure it through env vars
+#[derive(Clone, Default)]
+pub struct DatadogClientBuilder {
+    api_key: Option<String>,
+    app_key: Option<String>,
+    site: Option<String>,
+    timeout: Option<Duration>,
+    retries: u32,
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    user_agent: Option<String>,
+}
+
+/// Keys and header values are redacted, since they may hold credentials
+impl fmt::Debug for DatadogClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatadogClientBuilder")
+            .field("api_key", &self.api_key.as_ref().map(|_| REDACTED))
+            .field("app_key", &self.app_key.as_ref().map(|_| REDACTED))
+            .field("site", &self.site)
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .field("base_url", &self.base_url)
+            .field(
+                "headers",
+                &self
+                    .headers
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("user_agent", &self.user_agent)
+            .finish()
+    }
+}
+
+impl DatadogClientBuilder {
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn app_key(mut self, app_key: impl Into<String>) -> Self {
+        self.app_key = Some(app_key.into());
+        self
+    }
+
+    /// Datadog site domain or alias, e.g. `datadoghq.eu` or `eu`. Defaults to `datadoghq.com`.
+    pub fn site(mut self, site: impl Into<String>) -> Self {
+        self.site = Some(site.into());
+        self
+    }
+
+    /// Timeout for each HTTP request. Defaults to reqwest's 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry failed requests this many times. Defaults to 0.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Send requests to this URL instead of `https://api.{site}`, e.g. a proxy or test server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Add a header to every request, e.g. for a proxy in front of Datadog.
+    /// A header named like an auth header (`DD-API-KEY`) replaces the configured key.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// User-Agent to identify requests with. Defaults to `DEFAULT_USER_AGENT`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Start from the DD_API_KEY, DD_APP_KEY and DD_SITE environment variables
+    pub fn from_env() -> Result<Self, DatadogError> {
+        let api_key = env::var("DD_API_KEY").map_err(|_| {
+            DatadogError::Config("Missing environment variable: DD_API_KEY".to_string())
+        })?;
+        let app_key = env::var("DD_APP_KEY").map_err(|_| {
+            DatadogError::Config("Missing environment variable: DD_APP_KEY".to_string())
+        })?;
+        let site = site_from_env();
 
-        Ok(Self {
+        Ok(Self::default().api_key(api_key).app_key(app_key).site(site))
+    }
+
+    /// Whether the API key has the shape of an application key and the application key that
+    /// of an API key, so they were probably swapped. Only a hint for a warning: a wrong
+    /// guess must not stop keys that work.
+    pub fn keys_look_swapped(&self) -> bool {
+        match (&self.api_key, &self.app_key) {
+            (Some(api_key), Some(app_key)) => keys_look_swapped(api_key, app_key),
+            _ => false,
+        }
+    }
+
+    pub fn build(self) -> Result<DatadogClient, DatadogError> {
+        let api_key = self
+            .api_key
+            .ok_or_else(|| DatadogError::Config("Missing API key".to_string()))?;
+        let app_key = self
+            .app_key
+            .ok_or_else(|| DatadogError::Config("Missing application key".to_string()))?;
+        let site = self
+            .site
+            .map(|site| resolve_site(&site))
+            .unwrap_or_else(|| DEFAULT_SITE.to_string());
+        let base_url = match self.base_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => api_base_url(&site),
+        };
+
+        // Large log pages compress well, so ask for gzip/deflate and decode transparently
+        let mut client = reqwest::blocking::Client::builder()
+            .user_agent(
+                self.user_agent
+                    .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()),
+            )
+            .gzip(true)
+            .deflate(true);
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        let client = client
+            .build()
+            .map_err(|e| DatadogError::Config(format!("Failed to create HTTP client: {}", e)))?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| DatadogError::Config(format!("Invalid header name: {}", name)))?;
+            let header_value = HeaderValue::from_str(value.trim())
+                .map_err(|_| DatadogError::Config(format!("Invalid value for header {}", name)))?;
+            headers.append(header_name, header_value);
+        }
+
+        Ok(DatadogClient {
             api_key,
             app_key,
-            client: reqwest::blocking::Client::new(),
+            client,
+            site,
+            base_url,
+            retries: self.retries,
+            headers,
+            interrupt: None,
+            deadline: None,
+            verbosity: 0,
+            har: None,
+            rate_limiter: None,
         })
     }
+}
 
-    /// Search logs with streaming output. Calls `on_batch` with each page of results as they arrive.
-    /// Returns the total number of logs retrieved.
-    pub fn search_logs<F>(&self, query: &LogsQuery, mut on_batch: F) -> Result<usize, String>
-    where
-        F: FnMut(&[LogEntry]),
-    {
-        const MAX_PAGE_SIZE: u32 = 5000;
+impl DatadogClient {
+    /// Create a client from the DD_API_KEY, DD_APP_KEY and DD_SITE environment variables
+    pub fn new() -> Result<Self, DatadogError> {
+        DatadogClientBuilder::from_env()?.build()
+    }
 
-        let mut total_count: usize = 0;
-        let mut cursor: Option<String> = None;
+    pub fn builder() -> DatadogClientBuilder {
+        DatadogClientBuilder::default()
+    }
+
+    /// The Datadog site this client sends requests to
+    pub fn site(&self) -> &str {
+        &self.site
+    }
+
+    /// Full URL for an API path such as `/api/v2/logs/events/search`, using the
+    /// configured site or base URL
+    pub fn endpoint(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Set the verbosity of request logging to stderr
+    pub fn with_verbosity(mut self, verbosity: u8) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Stop paginated searches between pages once `flag` is set, e.g. from a Ctrl-C handler.
+    /// The search returns the results so far with `SearchSummary::interrupted` set.
+    pub fn with_interrupt(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.interrupt = Some(flag);
+        self
+    }
+
+    /// Stop paginated searches at `deadline`, returning the results fetched so far.
+    /// Unlike the builder's `timeout`, this bounds every page of a search together.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Record every request attempt, including retries, in `har`
+    pub fn with_har(mut self, har: Option<Arc<HarRecorder>>) -> Self {
+        self.har = har;
+        self
+    }
+
+    /// Send at most `per_second` requests per second, e.g. to stay under the API's rate
+    /// limits during a large export. None = no throttle.
+    pub fn with_rate_limit(mut self, per_second: Option<u32>) -> Self {
+        self.rate_limiter = per_second.map(|rate| Arc::new(RateLimiter::new(rate)));
+        self
+    }
+
+    pub(crate) fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    pub(crate) fn is_interrupted(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::SeqCst))
+    }
+
+    /// Send an authenticated request built by `request`, retrying connection errors,
+    /// 429s and 5xx responses up to `retries` times. Non-success responses become `DatadogError::Api`.
+    pub(crate) fn send(
+        &self,
+        method: &str,
+        url: &str,
+        request: impl Fn(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response, DatadogError> {
+        let mut attempt = 0;
 
         loop {
-            // Calculate page size: min(remaining, 5000)
-            let page_size = match query.limit {
-                Some(limit) => {
-                    let remaining = limit.saturating_sub(total_count as u32);
-                    remaining.min(MAX_PAGE_SIZE)
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.wait();
+            }
+            self.log_request(method, url);
+
+            let request = request(&self.client)
+                .headers(self.request_headers()?)
+                .build()
+                .map_err(DatadogError::Request)?;
+            let traced = self
+                .har
+                .as_ref()
+                .and_then(|_| request.try_clone())
+                .map(|traced| (traced, chrono::Utc::now(), Instant::now()));
+            let trace = |outcome: HarOutcome<'_>| {
+                if let (Some(har), Some((request, started, timer))) = (&self.har, &traced) {
+                    let redact = |name: &str| self.headers.contains_key(name);
+                    har.record(request, redact, *started, timer.elapsed(), outcome);
                 }
-                None => MAX_PAGE_SIZE,
             };
 
-            // If we've already collected enough, stop
-            if page_size == 0 {
-                break;
-            }
+            let result = self.client.execute(request);
 
-            let request_body = LogsSearchRequest {
-                filter: LogsFilter {
-                    query: query.query.clone(),
-                    from: query.from.clone(),
-                    to: query.to.clone(),
-                },
-                page: PageOptions {
-                    limit: page_size,
-                    cursor: cursor.clone(),
-                },
-                sort: "timestamp".to_string(),
+            let retryable = match &result {
+                Ok(response) => {
+                    let status = response.status();
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => e.is_connect() || e.is_timeout(),
             };
+            if retryable && attempt < self.retries {
+                match &result {
+                    Ok(response) => {
+                        trace(HarOutcome::Response {
+                            status: response.status(),
+                            headers: response.headers(),
+                            body: None,
+                        });
+                        self.log_response(response.status(), None);
+                    }
+                    Err(e) => trace(HarOutcome::Failed(e.to_string())),
+                }
+                attempt += 1;
+                self.backoff(attempt);
+                continue;
+            }
 
-            let response = self
-                .client
-                .post("https://api.datadoghq.com/api/v2/logs/events/search")
-                .header("DD-API-KEY", &self.api_key)
-                .header("DD-APPLICATION-KEY", &self.app_key)
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-                .map_err(|e| format!("Request failed: {}", e))?;
-
+            let response = result.map_err(|e| {
+                trace(HarOutcome::Failed(e.to_string()));
+                DatadogError::Request(e)
+            })?;
             if !response.status().is_success() {
                 let status = response.status();
+                let headers = response.headers().clone();
                 let body = response.text().unwrap_or_default();
-                return Err(format!("API error ({}): {}", status, body));
+                trace(HarOutcome::Response {
+                    status,
+                    headers: &headers,
+                    body: Some(&body),
+                });
+                self.log_response(status, Some(&body));
+                return Err(DatadogError::Api { status, body });
             }
+            trace(HarOutcome::Response {
+                status: response.status(),
+                headers: response.headers(),
+                body: None,
+            });
+            self.log_response(response.status(), None);
 
-            let internal_response: LogsSearchResponseInternal = response
-                .json()
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
+            return Ok(response);
+        }
+    }
 
-            // Stream logs from this page immediately
-            if let Some(logs) = internal_response.data {
-                on_batch(&logs);
-                total_count += logs.len();
-            }
+    /// Auth and content-type headers, with any user-supplied headers replacing them by name
+    fn request_headers(&self) -> Result<HeaderMap, DatadogError> {
+        let invalid = |name: &str| DatadogError::Config(format!("Invalid value for {}", name));
 
-            // Check for next page cursor
-            let next_cursor = internal_response
-                .meta
-                .and_then(|m| m.page)
-                .and_then(|p| p.after);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "DD-API-KEY",
+            HeaderValue::from_str(&self.api_key).map_err(|_| invalid("DD_API_KEY"))?,
+        );
+        headers.insert(
+            "DD-APPLICATION-KEY",
+            HeaderValue::from_str(&self.app_key).map_err(|_| invalid("DD_APP_KEY"))?,
+        );
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
-            match next_cursor {
-                Some(c) => cursor = Some(c),
-                None => break, // No more pages
-            }
+        // Extending replaces every value of a key present in the user headers
+        headers.extend(self.headers.clone());
+        Ok(headers)
+    }
 
-            // Check if we've collected enough
+    /// Like `send`, but also parses the JSON body. A body that is truncated or malformed
+    /// is re-requested up to `retries` times; API error responses are never retried here.
+    pub(crate) fn send_json<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        url: &str,
+        request: impl Fn(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder,
+    ) -> Result<T, DatadogError> {
+        let mut attempt = 0;
+
+        loop {
+            let result = self
+                .send(method, url, &request)?
+                .text()
+                .map_err(DatadogError::Request)
+                .and_then(|body| {
+                    serde_json::from_str(&body).map_err(|e| DatadogError::Parse(e.to_string()))
+                });
+
+            match result {
+                Err(e) if attempt < self.retries => {
+                    if self.verbosity > 0 {
+                        eprintln!("< {}, retrying", e);
+                    }
+                    attempt += 1;
+                    self.backoff(attempt);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sleep before retry number `attempt` (1-based), see `backoff_delay`
+    fn backoff(&self, attempt: u32) {
+        thread::sleep(backoff_delay(attempt));
+    }
+
+    /// Send an authenticated GET request and parse the JSON response
+    pub(crate) fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<T, DatadogError> {
+        self.send_json("GET", url, |client| client.get(url))
+    }
+
+    /// Log an outgoing request. Auth headers are always redacted.
+    pub(crate) fn log_request(&self, method: &str, url: &str) {
+        if self.verbosity == 0 {
+            return;
+        }
+        eprintln!("> {} {}", method, url);
+        eprintln!("> DD-API-KEY: ***");
+        eprintln!("> DD-APPLICATION-KEY: ***");
+    }
+
+    /// Log the pagination state of the next request
+    pub(crate) fn log_page(&self, page_size: u32, cursor: Option<&str>) {
+        if self.verbosity == 0 {
+            return;
+        }
+        eprintln!(
+            "> page size: {}, cursor: {}",
+            page_size,
+            cursor.unwrap_or("none")
+        );
+    }
+
+    /// Halve a page size the API rejected, returning the size to retry with
+    pub(crate) fn reduce_page_size(&self, rejected: u32) -> u32 {
+        let reduced = (rejected / 2).max(1);
+        debug_pagination("page_size_rejected", || {
+            format!("page_size={} retry_page_size={}", rejected, reduced)
+        });
+        if self.verbosity > 0 {
+            eprintln!(
+                "< page size {} rejected, retrying with {}",
+                rejected, reduced
+            );
+        }
+        reduced
+    }
+
+    /// Log the status of a response, and its body on error at verbosity 2+
+    pub(crate) fn log_response(&self, status: reqwest::StatusCode, error_body: Option<&str>) {
+        if self.verbosity == 0 {
+            return;
+        }
+        eprintln!("< {}", status);
+        if self.verbosity >= 2
+            && let Some(body) = error_body
+        {
+            eprintln!("< {}", body);
+        }
+    }
+
+    /// Search logs and collect every page into a single `Vec`.
+    /// Prefer `search_logs` for large result sets, since this holds all entries in memory.
+    pub fn search_logs_collect(&self, query: &LogsQuery) -> Result<Vec<LogEntry>, DatadogError> {
+        let mut entries = Vec::new();
+        self.search_logs(query, |batch| entries.extend(batch.iter().cloned()))?;
+        Ok(entries)
+    }
+
+    /// Run several searches on up to `concurrency` threads, collecting each query's entries
+    /// with the summary of its search. Results are in the same order as `queries`.
+    pub fn search_logs_batch(&self, queries: &[LogsQuery], concurrency: usize) -> Vec<BatchResult> {
+        let workers = concurrency.clamp(1, queries.len().max(1));
+        let next = AtomicUsize::new(0);
+
+        let mut results: Vec<(usize, BatchResult)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    let client = self.clone();
+                    let next = &next;
+                    scope.spawn(move || {
+                        let mut done = Vec::new();
+                        // Each worker takes the next unclaimed query until none are left
+                        loop {
+                            let index = next.fetch_add(1, Ordering::SeqCst);
+                            let Some(query) = queries.get(index) else {
+                                break;
+                            };
+                            let mut entries = Vec::new();
+                            let result = client
+                                .search_logs(query, |batch| entries.extend(batch.iter().cloned()))
+                                .map(|summary| (entries, summary));
+                            done.push((index, result));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("search thread panicked"))
+                .collect()
+        });
+
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Search logs with streaming output. Calls `on_batch` with each page of results as they arrive.
+    /// Returns a `SearchSummary` with the number of logs and pages fetched, whether pagination
+    /// stopped early (max pages, interrupt or deadline), and the cursor for any remaining page.
+    pub fn search_logs<F>(
+        &self,
+        query: &LogsQuery,
+        on_batch: F,
+    ) -> Result<SearchSummary, DatadogError>
+    where
+        F: FnMut(&[LogEntry]),
+    {
+        self.search_logs_with_progress(query, on_batch, |_| {})
+    }
+
+    /// Iterate over the logs matching `query`, fetching each page only once the previous
+    /// one has been consumed. Dropping the iterator stops fetching.
+    pub fn logs_iter<'a>(&'a self, query: &'a LogsQuery) -> LogsIter<'a> {
+        LogsIter {
+            client: self,
+            query,
+            buffer: VecDeque::new(),
+            cursor: query.cursor.clone(),
+            fetched: 0,
+            pages: 0,
+            max_page_size: query.page_size.unwrap_or(MAX_PAGE_SIZE),
+            done: false,
+        }
+    }
+
+    /// Request one page of logs
+    fn request_logs_page(
+        &self,
+        query: &LogsQuery,
+        page_size: u32,
+        cursor: Option<String>,
+    ) -> Result<LogsSearchResponseInternal, DatadogError> {
+        let request_body = query.search_request(page_size, cursor.clone());
+        let url = self.endpoint(LOGS_SEARCH_PATH);
+        self.log_page(page_size, cursor.as_deref());
+
+        self.send_json("POST", &url, |client| client.post(&url).json(&request_body))
+    }
+
+    /// Like `search_logs`, but also calls `on_page` with the running totals after each page.
+    pub fn search_logs_with_progress<F, P>(
+        &self,
+        query: &LogsQuery,
+        mut on_batch: F,
+        mut on_page: P,
+    ) -> Result<SearchSummary, DatadogError>
+    where
+        F: FnMut(&[LogEntry]),
+        P: FnMut(&SearchSummary),
+    {
+        validate_time_range(&query.from, &query.to, chrono::Utc::now())
+            .map_err(DatadogError::Config)?;
+
+        let mut total_count: usize = 0;
+        let mut pages: usize = 0;
+        let mut cursor: Option<String> = query.cursor.clone();
+        let mut truncated = false;
+        let mut interrupted = false;
+        let mut timed_out = false;
+        // Shrinks whenever the API rejects a page as too large, and stays shrunk
+        let mut max_page_size = query.page_size.unwrap_or(MAX_PAGE_SIZE);
+
+        let stop_reason = loop {
+            // Stop between pages once interrupted, so the last batch is fully delivered
+            if self.is_interrupted() {
+                interrupted = true;
+                break "interrupted";
+            }
+            if self.is_past_deadline() {
+                timed_out = true;
+                break "deadline";
+            }
+
+            let page_size = page_size(query.limit, total_count).min(max_page_size);
+
+            // If we've already collected enough, stop
+            if page_size == 0 {
+                break "limit_reached";
+            }
+            debug_pagination("request", || {
+                format!(
+                    "page_size={} max_page_size={} total_count={} cursor={}",
+                    page_size,
+                    max_page_size,
+                    total_count,
+                    cursor.is_some()
+                )
+            });
+
+            let internal_response = match self.request_logs_page(query, page_size, cursor.clone()) {
+                Err(e) if e.is_page_limit_error() && page_size > 1 => {
+                    max_page_size = self.reduce_page_size(page_size);
+                    continue;
+                }
+                result => result?,
+            };
+            pages += 1;
+
+            // Stream logs from this page immediately
+            if let Some(logs) = internal_response.data {
+                on_batch(&logs);
+                total_count += logs.len();
+            }
+
+            // Check for next page cursor
+            cursor = internal_response
+                .meta
+                .and_then(|m| m.page)
+                .and_then(|p| p.after);
+            debug_pagination("response", || {
+                format!(
+                    "page={} total_count={} next_cursor={}",
+                    pages,
+                    total_count,
+                    cursor.is_some()
+                )
+            });
+
+            on_page(&SearchSummary {
+                total: total_count,
+                pages,
+                cursor: cursor.clone(),
+                ..Default::default()
+            });
+
+            if cursor.is_none() {
+                break "no_cursor"; // No more pages
+            }
+
+            // Check if we've collected enough
             if let Some(limit) = query.limit
                 && total_count >= limit as usize
             {
-                break;
+                break "limit_reached";
+            }
+
+            if let Some(max_pages) = query.max_pages
+                && pages >= max_pages as usize
+            {
+                truncated = true;
+                break "max_pages";
             }
+        };
+        debug_pagination("stop", || {
+            format!(
+                "reason={} total_count={} pages={}",
+                stop_reason, total_count, pages
+            )
+        });
+
+        Ok(SearchSummary {
+            total: total_count,
+            pages,
+            truncated,
+            interrupted,
+            timed_out,
+            cursor,
+        })
+    }
+}
+
+/// Logs matching a query, fetched a page at a time as they are consumed. Created by
+/// `DatadogClient::logs_iter`.
+///
+/// Iteration ends after `limit` logs, `max_pages` pages, or the last page, and early
+/// (without an error) once the client is interrupted or past its deadline. A failed
+/// request is yielded as an error, after which iteration ends.
+#[derive(Debug)]
+pub struct LogsIter<'a> {
+    client: &'a DatadogClient,
+    query: &'a LogsQuery,
+    buffer: VecDeque<LogEntry>,
+    cursor: Option<String>,
+    fetched: usize,
+    pages: usize,
+    max_page_size: u32,
+    done: bool,
+}
+
+impl LogsIter<'_> {
+    /// Fetch the next page into the buffer, setting `done` when no more pages follow
+    fn fetch_page(&mut self) -> Result<(), DatadogError> {
+        if self.pages == 0 {
+            validate_time_range(&self.query.from, &self.query.to, chrono::Utc::now())
+                .map_err(DatadogError::Config)?;
         }
 
-        Ok(total_count)
+        loop {
+            if self.client.is_interrupted() || self.client.is_past_deadline() {
+                self.stop(if self.client.is_interrupted() {
+                    "interrupted"
+                } else {
+                    "deadline"
+                });
+                return Ok(());
+            }
+
+            let page_size = page_size(self.query.limit, self.fetched).min(self.max_page_size);
+            if page_size == 0 {
+                self.stop("limit_reached");
+                return Ok(());
+            }
+            debug_pagination("request", || {
+                format!(
+                    "page_size={} max_page_size={} total_count={} cursor={}",
+                    page_size,
+                    self.max_page_size,
+                    self.fetched,
+                    self.cursor.is_some()
+                )
+            });
+
+            let response =
+                match self
+                    .client
+                    .request_logs_page(self.query, page_size, self.cursor.clone())
+                {
+                    Err(e) if e.is_page_limit_error() && page_size > 1 => {
+                        self.max_page_size = self.client.reduce_page_size(page_size);
+                        continue;
+                    }
+                    result => result?,
+                };
+            self.pages += 1;
+
+            let logs = response.data.unwrap_or_default();
+            self.fetched += logs.len();
+            self.buffer.extend(logs);
+            self.cursor = response.meta.and_then(|m| m.page).and_then(|p| p.after);
+            debug_pagination("response", || {
+                format!(
+                    "page={} total_count={} next_cursor={}",
+                    self.pages,
+                    self.fetched,
+                    self.cursor.is_some()
+                )
+            });
+
+            let limit_reached = self
+                .query
+                .limit
+                .is_some_and(|limit| self.fetched >= limit as usize);
+            let pages_reached = self
+                .query
+                .max_pages
+                .is_some_and(|max_pages| self.pages >= max_pages as usize);
+            if self.cursor.is_none() {
+                self.stop("no_cursor");
+            } else if limit_reached {
+                self.stop("limit_reached");
+            } else if pages_reached {
+                self.stop("max_pages");
+            }
+            return Ok(());
+        }
+    }
+
+    /// End iteration once the buffer drains, noting why for DATADOG_CLI_DEBUG
+    fn stop(&mut self, reason: &str) {
+        self.done = true;
+        debug_pagination("stop", || {
+            format!(
+                "reason={} total_count={} pages={}",
+                reason, self.fetched, self.pages
+            )
+        });
     }
 }
 
-pub fn format_log_entry(entry: &LogEntry) -> String {
-    let timestamp = entry
-        .attributes
-        .timestamp
-        .as_ref()
-        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
-        .map(|dt| {
-            dt.with_timezone(&Utc)
-                .format("%Y-%m-%d %H:%M:%S")
-                .to_string()
-        })
-        .unwrap_or_else(|| "--------------------".to_string());
+impl Iterator for LogsIter<'_> {
+    type Item = Result<LogEntry, DatadogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // An empty page with a cursor is possible, so keep fetching until one has logs
+        while self.buffer.is_empty() && !self.done {
+            if let Err(e) = self.fetch_page() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+/// Layout options for `format_log_entry`. The default matches the standard CLI output.
+#[derive(Debug, Clone)]
+pub struct LogFormatOptions {
+    /// Emit ANSI colors (still subject to `colored`'s global override)
+    pub color: bool,
+    /// Timezone for the displayed timestamp
+    pub timezone: DisplayTimezone,
+    /// How the timestamp is rendered
+    pub time_format: TimeFormat,
+    /// Extra fields shown as `key=value` between the status and the message
+    pub fields: Vec<String>,
+    /// Minimum width the status column is padded to
+    pub status_width: usize,
+    /// Highlight matches of this pattern in the message
+    pub highlight: Option<Regex>,
+    /// Append every custom attribute as `key=value` after the message
+    pub show_attributes: bool,
+    /// Truncate the message to this many columns, ending with an ellipsis
+    pub max_width: Option<usize>,
+}
+
+impl Default for LogFormatOptions {
+    fn default() -> Self {
+        Self {
+            color: true,
+            timezone: DisplayTimezone::Utc,
+            time_format: TimeFormat::Absolute,
+            fields: Vec::new(),
+            status_width: 5,
+            highlight: None,
+            show_attributes: false,
+            max_width: None,
+        }
+    }
+}
+
+pub fn format_log_entry(entry: &LogEntry, options: &LogFormatOptions) -> String {
+    let paint = |s: ColoredString| if options.color { s } else { s.clear() };
+    let timestamp = display_timestamp(
+        entry.attributes.timestamp.as_deref(),
+        options.timezone,
+        options.time_format,
+        chrono::Utc::now(),
+    );
 
     let status_raw = entry
         .attributes
@@ -217,21 +1600,1045 @@ pub fn format_log_entry(entry: &LogEntry) -> String {
         .map(|s| s.to_uppercase())
         .unwrap_or_else(|| "-----".to_string());
 
-    let status_colored = match status_raw.as_str() {
-        "ERROR" | "CRITICAL" | "EMERGENCY" | "ALERT" => format!("{:5}", status_raw).red().bold(),
-        "WARN" | "WARNING" => format!("{:5}", status_raw).yellow(),
-        "INFO" => format!("{:5}", status_raw).green(),
-        "DEBUG" => format!("{:5}", status_raw).blue(),
-        "TRACE" => format!("{:5}", status_raw).cyan(),
-        _ => format!("{:5}", status_raw).normal(),
-    };
+    let status = format!("{:width$}", status_raw, width = options.status_width);
+    let status_colored = paint(color_status(&status));
 
     let message = entry.attributes.message.as_deref().unwrap_or("");
+    let message = match options.max_width {
+        Some(width) => truncate_to_width(message, width),
+        None => message.into(),
+    };
+    let message = match &options.highlight {
+        Some(pattern) if options.color => highlight_matches(&message, pattern),
+        _ => message.to_string(),
+    };
+
+    let fields: String = options
+        .fields
+        .iter()
+        .map(|name| {
+            let value = lookup_field(entry, name, options.timezone);
+            let value = if value.is_empty() {
+                "-".to_string()
+            } else {
+                value
+            };
+            format!("{}={} | ", name, value)
+        })
+        .collect();
 
-    format!(
-        "[{}] {} | {}",
-        timestamp.bright_black(),
+    let mut line = format!(
+        "[{}] {} | {}{}",
+        paint(timestamp.bright_black()),
         status_colored,
+        fields,
         message
+    );
+
+    if options.show_attributes {
+        for (key, value) in entry.attributes.custom_attributes() {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => serde_json::to_string(other).unwrap_or_default(),
+            };
+            line.push_str(&format!(
+                " {}",
+                paint(format!("{}={}", key, value).bright_black())
+            ));
+        }
+    }
+
+    line
+}
+
+/// Column names of the rows written by `format_log_csv`
+pub const LOG_CSV_COLUMNS: [&str; 5] = ["timestamp", "status", "service", "host", "message"];
+
+/// Render an entry as one CSV record of `LOG_CSV_COLUMNS`. Messages keep their newlines
+/// inside a quoted field.
+pub fn format_log_csv(entry: &LogEntry, tz: DisplayTimezone) -> String {
+    let attrs = &entry.attributes;
+    // Leave the cell empty rather than using the text layout's dashed placeholder
+    let timestamp = attrs
+        .timestamp
+        .as_deref()
+        .map(|ts| format_timestamp(Some(ts), tz))
+        .unwrap_or_default();
+    csv_record(&[
+        &timestamp,
+        attrs.status.as_deref().unwrap_or(""),
+        attrs.service.as_deref().unwrap_or(""),
+        attrs.host.as_deref().unwrap_or(""),
+        attrs.message.as_deref().unwrap_or(""),
+    ])
+}
+
+/// Render entries as an aligned table of timestamp, status, service and message
+pub fn format_log_table(entries: &[LogEntry], tz: DisplayTimezone) -> String {
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            let attrs = &entry.attributes;
+            vec![
+                format_timestamp(attrs.timestamp.as_deref(), tz),
+                attrs.status.as_deref().unwrap_or("-").to_uppercase(),
+                attrs.service.clone().unwrap_or_else(|| "-".to_string()),
+                attrs.message.as_deref().unwrap_or("").replace('\n', " "),
+            ]
+        })
+        .collect();
+
+    format_table(
+        &["TIMESTAMP", "STATUS", "SERVICE", "MESSAGE"],
+        &rows,
+        Some(1),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Page sizes requested for `limit`, assuming every page comes back full
+    fn page_sizes(limit: u32) -> Vec<u32> {
+        let mut sizes = Vec::new();
+        let mut fetched = 0;
+        loop {
+            let size = page_size(Some(limit), fetched);
+            if size == 0 {
+                break;
+            }
+            sizes.push(size);
+            fetched += size as usize;
+        }
+        sizes
+    }
+
+    #[rstest]
+    #[case(1, vec![1])]
+    #[case(4999, vec![4999])]
+    #[case(5000, vec![5000])]
+    #[case(5001, vec![5000, 1])]
+    #[case(10001, vec![5000, 5000, 1])]
+    fn test_page_sizes_at_boundaries(#[case] limit: u32, #[case] expected: Vec<u32>) {
+        assert_eq!(page_sizes(limit), expected);
+    }
+
+    fn sample_entry() -> LogEntry {
+        serde_json::from_value(serde_json::json!({
+            "attributes": {
+                "timestamp": "2024-01-01T12:00:00Z",
+                "status": "error",
+                "message": "boom",
+                "service": "api",
+                "attributes": { "duration": 42, "http": { "status_code": 500 } }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case(LogFormatOptions::default(), "[2024-01-01 12:00:00] ERROR | boom")]
+    #[case(
+        LogFormatOptions { status_width: 8, ..Default::default() },
+        "[2024-01-01 12:00:00] ERROR    | boom"
+    )]
+    #[case(
+        LogFormatOptions {
+            fields: vec!["service".to_string(), "duration".to_string(), "host".to_string()],
+            ..Default::default()
+        },
+        "[2024-01-01 12:00:00] ERROR | service=api | duration=42 | host=- | boom"
+    )]
+    #[case(
+        LogFormatOptions { show_attributes: true, ..Default::default() },
+        r#"[2024-01-01 12:00:00] ERROR | boom duration=42 http={"status_code":500}"#
+    )]
+    fn test_format_log_entry_layout(#[case] options: LogFormatOptions, #[case] expected: &str) {
+        let options = LogFormatOptions {
+            color: false,
+            ..options
+        };
+
+        assert_eq!(format_log_entry(&sample_entry(), &options), expected);
+    }
+
+    #[rstest]
+    #[case(
+        DisplayTimezone::Utc,
+        "2024-01-01 12:00:00,error,api,,\"boom, again\nand again\""
+    )]
+    #[case(
+        DisplayTimezone::Named(chrono_tz::Asia::Tokyo),
+        "2024-01-01 21:00:00,error,api,,\"boom, again\nand again\""
+    )]
+    fn test_format_log_csv(#[case] tz: DisplayTimezone, #[case] expected: &str) {
+        let mut entry = sample_entry();
+        entry.attributes.message = Some("boom, again\nand again".to_string());
+
+        assert_eq!(format_log_csv(&entry, tz), expected);
+    }
+
+    /// Serve each body as a 200 JSON response to one request in turn, returning the base URL
+    fn serve(bodies: Vec<&'static str>) -> String {
+        serve_with(
+            bodies
+                .into_iter()
+                .map(|body| (String::new(), body.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    /// Like `serve`, but each response carries extra header lines and a raw body
+    fn serve_with(responses: Vec<(String, Vec<u8>)>) -> String {
+        let count = responses.len();
+        let mut responses = responses.into_iter();
+        serve_requests(count, move |_| responses.next().unwrap())
+    }
+
+    /// Answer `count` requests in turn with `respond`, which gets the request body and
+    /// returns extra header lines and a raw response body
+    fn serve_requests(
+        count: usize,
+        mut respond: impl FnMut(&[u8]) -> (String, Vec<u8>) + Send + 'static,
+    ) -> String {
+        serve_statuses(count, move |body| {
+            let (headers, body) = respond(body);
+            (reqwest::StatusCode::OK, headers, body)
+        })
+    }
+
+    /// Like `serve_requests`, but `respond` also picks the response status
+    fn serve_statuses(
+        count: usize,
+        mut respond: impl FnMut(&[u8]) -> (reqwest::StatusCode, String, Vec<u8>) + Send + 'static,
+    ) -> String {
+        serve_raw(count, move |_, body| respond(body))
+    }
+
+    /// Like `serve_statuses`, but `respond` also gets the request's header lines
+    fn serve_raw(
+        count: usize,
+        mut respond: impl FnMut(&[String], &[u8]) -> (reqwest::StatusCode, String, Vec<u8>)
+        + Send
+        + 'static,
+    ) -> String {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for _ in 0..count {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                // Consume the request so closing the socket doesn't reset the connection
+                let mut content_length = 0;
+                let mut request_headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    request_headers.push(line.trim_end().to_string());
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+
+                let (status, extra_headers, body) = respond(&request_headers, &request_body);
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    extra_headers,
+                    body.len()
+                );
+                let stream = reader.get_mut();
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+
+        base_url
+    }
+
+    fn test_client(base_url: &str, retries: u32) -> DatadogClient {
+        DatadogClient::builder()
+            .api_key("api")
+            .app_key("app")
+            .base_url(base_url)
+            .retries(retries)
+            .build()
+            .unwrap()
+    }
+
+    const TRUNCATED_PAGE: &str = r#"{"data": [{"id": "a", "attributes": {"#;
+    const GOOD_PAGE: &str =
+        r#"{"data": [{"id": "a", "attributes": {}}, {"id": "b", "attributes": {}}]}"#;
+
+    #[test]
+    fn test_search_logs_retries_truncated_page() {
+        let client = test_client(&serve(vec![TRUNCATED_PAGE, GOOD_PAGE]), 1);
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        let mut batches = Vec::new();
+        let summary = client
+            .search_logs(&query, |batch| batches.push(batch.len()))
+            .unwrap();
+
+        assert_eq!(batches, vec![2]);
+        assert_eq!(
+            summary,
+            SearchSummary {
+                total: 2,
+                pages: 1,
+                truncated: false,
+                interrupted: false,
+                timed_out: false,
+                cursor: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_har_records_each_attempt() {
+        let mut statuses = vec![
+            reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            reqwest::StatusCode::OK,
+        ];
+        let base_url = serve_statuses(2, move |_| {
+            (
+                statuses.remove(0),
+                String::new(),
+                GOOD_PAGE.as_bytes().to_vec(),
+            )
+        });
+        let har = Arc::new(HarRecorder::new());
+        let client = test_client(&base_url, 1).with_har(Some(har.clone()));
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        client.search_logs(&query, |_| {}).unwrap();
+
+        let log = har.to_json();
+        let entries = log["log"]["entries"].as_array().unwrap();
+        let statuses: Vec<&serde_json::Value> =
+            entries.iter().map(|e| &e["response"]["status"]).collect();
+        assert_eq!(statuses, vec![503, 200]);
+        assert!(!log.to_string().contains("\"app\""));
+    }
+
+    #[test]
+    fn test_search_logs_decodes_gzip_response() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(GOOD_PAGE.as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+        let client = test_client(
+            &serve_with(vec![("Content-Encoding: gzip\r\n".to_string(), body)]),
+            0,
+        );
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        let entries = client.search_logs_collect(&query).unwrap();
+
+        let ids: Vec<_> = entries.iter().map(|e| e.id.as_deref()).collect();
+        assert_eq!(ids, vec![Some("a"), Some("b")]);
+    }
+
+    #[test]
+    fn test_client_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DatadogClient>();
+    }
+
+    #[test]
+    fn test_search_logs_batch_preserves_query_order() {
+        // Reply with one log whose id is the request's query
+        let base_url = serve_requests(4, |body| {
+            let request: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let page = serde_json::json!({
+                "data": [{ "id": request["filter"]["query"], "attributes": {} }]
+            });
+            (String::new(), page.to_string().into_bytes())
+        });
+        let client = test_client(&base_url, 0);
+        let queries: Vec<LogsQuery> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|q| LogsQuery::new(q.to_string(), "now-15m".into(), "now".into(), Some(1)))
+            .collect();
+
+        let results = client.search_logs_batch(&queries, 3);
+
+        let (ids, pages): (Vec<_>, Vec<_>) = results
+            .into_iter()
+            .map(|result| {
+                let (entries, summary) = result.unwrap();
+                (entries[0].id.clone().unwrap(), summary.pages)
+            })
+            .unzip();
+        assert_eq!(ids, vec!["a", "b", "c", "d"]);
+        assert_eq!(pages, vec![1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_search_logs_stops_at_max_pages() {
+        let first_page =
+            r#"{"data": [{"id": "a", "attributes": {}}], "meta": {"page": {"after": "next"}}}"#;
+        let client = test_client(&serve(vec![first_page]), 0);
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None)
+            .with_max_pages(Some(1));
+
+        let summary = client.search_logs(&query, |_| {}).unwrap();
+
+        assert_eq!(
+            summary,
+            SearchSummary {
+                total: 1,
+                pages: 1,
+                truncated: true,
+                interrupted: false,
+                timed_out: false,
+                cursor: Some("next".into())
+            }
+        );
+    }
+
+    #[test]
+    fn test_logs_iter_fetches_pages_lazily() {
+        let pages = [
+            r#"{"data": [{"id": "a", "attributes": {}}, {"id": "b", "attributes": {}}], "meta": {"page": {"after": "p2"}}}"#,
+            r#"{"data": [{"id": "c", "attributes": {}}], "meta": {"page": {"after": "p3"}}}"#,
+            r#"{"data": [{"id": "d", "attributes": {}}]}"#,
+        ];
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base_url = serve_requests(3, move |_| {
+            let page = pages[counter.fetch_add(1, Ordering::SeqCst)];
+            (String::new(), page.as_bytes().to_vec())
+        });
+        let client = test_client(&base_url, 0);
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        let mut logs = client.logs_iter(&query);
+        let first_two: Vec<String> = logs
+            .by_ref()
+            .take(2)
+            .map(|log| log.unwrap().id.unwrap())
+            .collect();
+        assert_eq!(first_two, vec!["a", "b"]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        assert_eq!(logs.next().unwrap().unwrap().id.as_deref(), Some("c"));
+        drop(logs);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_logs_iter_stops_at_limit_and_on_error() {
+        let page =
+            r#"{"data": [{"id": "a", "attributes": {}}], "meta": {"page": {"after": "next"}}}"#;
+        let client = test_client(&serve(vec![page]), 0);
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), Some(1));
+        let ids: Vec<_> = client
+            .logs_iter(&query)
+            .map(|log| log.unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![Some("a".to_string())]);
+
+        let base_url = serve_statuses(1, |_| {
+            (
+                reqwest::StatusCode::FORBIDDEN,
+                String::new(),
+                b"{}".to_vec(),
+            )
+        });
+        let client = test_client(&base_url, 0);
+        let mut logs = client.logs_iter(&query);
+        assert!(matches!(logs.next(), Some(Err(DatadogError::Api { .. }))));
+        assert!(logs.next().is_none());
+    }
+
+    #[test]
+    fn test_search_logs_stops_when_interrupted() {
+        let first_page =
+            r#"{"data": [{"id": "a", "attributes": {}}], "meta": {"page": {"after": "next"}}}"#;
+        let flag = Arc::new(AtomicBool::new(false));
+        let client = test_client(&serve(vec![first_page]), 0).with_interrupt(flag.clone());
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        let summary = client
+            .search_logs(&query, |_| flag.store(true, Ordering::SeqCst))
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            SearchSummary {
+                total: 1,
+                pages: 1,
+                truncated: false,
+                interrupted: true,
+                timed_out: false,
+                cursor: Some("next".into())
+            }
+        );
+    }
+
+    #[test]
+    fn test_search_logs_stops_at_deadline() {
+        let first_page =
+            r#"{"data": [{"id": "a", "attributes": {}}], "meta": {"page": {"after": "next"}}}"#;
+        let client = test_client(&serve(vec![first_page]), 0)
+            .with_deadline(Some(Instant::now() + Duration::from_millis(300)));
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        // Outlast the deadline while handling the first page, so no second page is requested
+        let summary = client
+            .search_logs(&query, |_| thread::sleep(Duration::from_millis(400)))
+            .unwrap();
+
+        assert_eq!(summary.total, 1);
+        assert!(summary.timed_out);
+        assert_eq!(summary.cursor.as_deref(), Some("next"));
+    }
+
+    #[test]
+    fn test_search_logs_past_deadline_requests_nothing() {
+        // No server: a request would fail to connect
+        let client = test_client("http://127.0.0.1:1", 0).with_deadline(Some(Instant::now()));
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        let summary = client.search_logs(&query, |_| {}).unwrap();
+
+        assert_eq!((summary.total, summary.pages), (0, 0));
+        assert!(summary.timed_out);
+    }
+
+    #[test]
+    fn test_search_logs_resumes_from_cursor() {
+        // Only answer with results when asked for the saved cursor
+        let base_url = serve_requests(1, |body| {
+            let request: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let page = match request["page"]["cursor"].as_str() {
+                Some("saved") => GOOD_PAGE,
+                _ => r#"{"data": []}"#,
+            };
+            (String::new(), page.as_bytes().to_vec())
+        });
+        let client = test_client(&base_url, 0);
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None)
+            .with_cursor(Some("saved".into()));
+
+        let mut cursors = Vec::new();
+        let summary = client
+            .search_logs_with_progress(&query, |_| {}, |page| cursors.push(page.cursor.clone()))
+            .unwrap();
+
+        assert_eq!(summary.total, 2);
+        assert_eq!(cursors, vec![None]);
+    }
+
+    #[test]
+    fn test_search_logs_halves_rejected_page_size() {
+        // Reject pages over 1000 like an account with a lower cap, recording each request's size
+        let sizes = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = sizes.clone();
+        let base_url = serve_statuses(5, move |body| {
+            let request: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let size = request["page"]["limit"].as_u64().unwrap();
+            recorded.lock().unwrap().push(size);
+            match (size, request["page"]["cursor"].as_str()) {
+                (1001.., _) => (
+                    reqwest::StatusCode::BAD_REQUEST,
+                    String::new(),
+                    br#"{"errors": ["page[limit] must be at most 1000"]}"#.to_vec(),
+                ),
+                (_, None) => (
+                    reqwest::StatusCode::OK,
+                    String::new(),
+                    br#"{"data": [{"id": "a", "attributes": {}}], "meta": {"page": {"after": "next"}}}"#.to_vec(),
+                ),
+                (_, Some(_)) => (reqwest::StatusCode::OK, String::new(), GOOD_PAGE.into()),
+            }
+        });
+        let client = test_client(&base_url, 0);
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        let summary = client.search_logs(&query, |_| {}).unwrap();
+
+        assert_eq!(summary.total, 3);
+        assert_eq!(*sizes.lock().unwrap(), vec![5000, 2500, 1250, 625, 625]);
+    }
+
+    #[test]
+    fn test_search_logs_fails_on_truncated_page_without_retries() {
+        let client = test_client(&serve(vec![TRUNCATED_PAGE]), 0);
+        let query = LogsQuery::new("*".into(), "now-15m".into(), "now".into(), None);
+
+        let result = client.search_logs(&query, |_| {});
+
+        assert!(matches!(result, Err(DatadogError::Parse(_))));
+    }
+
+    #[test]
+    fn test_log_summary_serializes_minimal_fields() {
+        let summary = serde_json::to_value(sample_entry().summary()).unwrap();
+
+        assert_eq!(
+            summary,
+            serde_json::json!({
+                "ts": "2024-01-01T12:00:00Z",
+                "status": "error",
+                "service": "api",
+                "message": "boom"
+            })
+        );
+    }
+
+    fn attributes(value: serde_json::Value) -> LogAttributes {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_typed_accessors_read_nested_attributes() {
+        let attrs = attributes(serde_json::json!({
+            "status": "info",
+            "attributes": {
+                "http": { "method": "POST", "status_code": 502, "url": "/checkout" },
+                "duration": 1250000000u64,
+                "dd": { "trace_id": "8734521009" }
+            }
+        }));
+
+        assert_eq!(attrs.http_method(), Some("POST"));
+        assert_eq!(attrs.http_status(), Some(502));
+        assert_eq!(attrs.duration_ns(), Some(1_250_000_000));
+        assert_eq!(attrs.trace_id().as_deref(), Some("8734521009"));
+    }
+
+    #[test]
+    fn test_typed_accessors_coerce_strings_and_flat_keys() {
+        let attrs = attributes(serde_json::json!({
+            "http.status_code": "404",
+            "duration": "1500.7",
+            "trace_id": 12345
+        }));
+
+        assert_eq!(attrs.http_method(), None);
+        assert_eq!(attrs.http_status(), Some(404));
+        assert_eq!(attrs.duration_ns(), Some(1500));
+        assert_eq!(attrs.trace_id().as_deref(), Some("12345"));
+    }
+
+    #[rstest]
+    #[case(serde_json::json!({ "http": { "status_code": 70000 } }))]
+    #[case(serde_json::json!({ "http": { "status_code": "n/a" } }))]
+    #[case(serde_json::json!({ "http": { "status_code": -1 } }))]
+    #[case(serde_json::json!({ "http": "not an object" }))]
+    #[case(serde_json::json!({}))]
+    fn test_http_status_rejects_invalid_values(#[case] value: serde_json::Value) {
+        assert_eq!(attributes(value).http_status(), None);
+    }
+
+    #[test]
+    fn test_user_headers_replace_defaults_by_name() {
+        let client = DatadogClient::builder()
+            .api_key("api")
+            .app_key("app")
+            .header("X-Proxy-Token", "secret")
+            .header("dd-api-key", "override")
+            .build()
+            .unwrap();
+
+        let headers = client.request_headers().unwrap();
+
+        assert_eq!(headers["x-proxy-token"], "secret");
+        assert_eq!(headers.get_all("DD-API-KEY").iter().count(), 1);
+        assert_eq!(headers["DD-API-KEY"], "override");
+        assert_eq!(headers["DD-APPLICATION-KEY"], "app");
+    }
+
+    #[rstest]
+    #[case(None, DEFAULT_USER_AGENT)]
+    #[case(Some("my-script/1.0"), "my-script/1.0")]
+    fn test_requests_send_user_agent(#[case] configured: Option<&str>, #[case] expected: &str) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let base_url = serve_raw(1, move |headers, _| {
+            tx.send(headers.to_vec()).unwrap();
+            (
+                reqwest::StatusCode::OK,
+                String::new(),
+                br#"{"data":[]}"#.to_vec(),
+            )
+        });
+        let mut builder = DatadogClient::builder()
+            .api_key("api")
+            .app_key("app")
+            .base_url(base_url);
+        if let Some(user_agent) = configured {
+            builder = builder.user_agent(user_agent);
+        }
+        let client = builder.build().unwrap();
+
+        let query = LogsQuery::new("*".into(), "now-1h".into(), "now".into(), Some(1));
+        client.search_logs(&query, |_| {}).unwrap();
+
+        let headers = rx.recv().unwrap();
+        assert!(
+            headers
+                .iter()
+                .any(|line| line.eq_ignore_ascii_case(&format!("user-agent: {}", expected))),
+            "{:?}",
+            headers
+        );
+    }
+
+    #[rstest]
+    #[case("0123456789abcdef0123456789ABCDEF", true)]
+    #[case(" 0123456789abcdef0123456789abcdef\n", true)]
+    #[case("0123456789abcdef0123456789abcdef01234567", false)]
+    #[case("0123456789abcdef0123456789abcdeg", false)]
+    #[case("", false)]
+    fn test_looks_like_api_key(#[case] key: &str, #[case] expected: bool) {
+        assert_eq!(looks_like_api_key(key), expected);
+    }
+
+    #[rstest]
+    #[case(&"a".repeat(40), &"b".repeat(32), true)]
+    #[case(&"a".repeat(32), &"b".repeat(40), false)]
+    #[case(&"a".repeat(40), "not-a-key", false)]
+    #[case("api", "app", false)]
+    fn test_keys_look_swapped(
+        #[case] api_key: &str,
+        #[case] app_key: &str,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(keys_look_swapped(api_key, app_key), expected);
+    }
+
+    #[test]
+    fn test_builder_reports_swapped_keys() {
+        let builder = DatadogClient::builder()
+            .api_key("b".repeat(40))
+            .app_key("a".repeat(32));
+
+        assert!(builder.keys_look_swapped());
+        assert!(!DatadogClient::builder().api_key("api").keys_look_swapped());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_header() {
+        let result = DatadogClient::builder()
+            .api_key("api")
+            .app_key("app")
+            .header("Bad Header", "value")
+            .build();
+
+        assert!(matches!(result, Err(DatadogError::Config(msg)) if msg.contains("Bad Header")));
+    }
+
+    #[test]
+    fn test_request_body_encodes_query() {
+        let query = LogsQuery::new(
+            r#"service:api "quoted \ text" @http.url:/a?b=c&d"#.into(),
+            "now-1h".into(),
+            "now".into(),
+            Some(10),
+        )
+        .with_indexes(vec!["main".into()]);
+
+        assert_eq!(
+            query.request_body(),
+            serde_json::json!({
+                "filter": {
+                    "query": r#"service:api "quoted \ text" @http.url:/a?b=c&d"#,
+                    "from": "now-1h",
+                    "to": "now",
+                    "indexes": ["main"]
+                },
+                "page": { "limit": 10 },
+                "sort": "timestamp"
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("query=service:api;from=now-1h;to=now;limit=500")]
+    #[case(r"query=a\;b \\ c;from=now-15m;to=now;sort=-timestamp;indexes=main,archive")]
+    #[case("query=*;from=2024-01-01T00:00:00+00:00;to=now")]
+    fn test_query_spec_round_trips(#[case] spec: &str) {
+        let query: LogsQuery = spec.parse().unwrap();
+
+        assert_eq!(query.to_string(), spec);
+    }
+
+    #[test]
+    fn test_query_spec_fills_defaults() {
+        let query: LogsQuery = "limit=5; query=status:error ;from=-2h".parse().unwrap();
+
+        assert_eq!(query.query, "status:error ");
+        assert_eq!((query.from.as_str(), query.to.as_str()), ("now-2h", "now"));
+        assert_eq!(query.limit, Some(5));
+        assert_eq!(query.sort, None);
+    }
+
+    #[rstest]
+    #[case("from=now-1h", "missing query")]
+    #[case("query=*;color=red", "Unknown query spec key: color")]
+    #[case("query=*;limit", "expected key=value")]
+    #[case("query=*;limit=lots", "Invalid limit")]
+    #[case("query=*;query=x", "Duplicate query spec key: query")]
+    #[case("query=*;from=tomorrow", "Invalid time")]
+    #[case(r"query=a\b", "Invalid escape")]
+    fn test_query_spec_rejects_invalid(#[case] spec: &str, #[case] expected: &str) {
+        let err = spec.parse::<LogsQuery>().unwrap_err();
+
+        assert!(err.contains(expected), "{}", err);
+    }
+
+    #[test]
+    fn test_request_body_includes_timezone() {
+        let query = LogsQuery::new("*".into(), "now-1h".into(), "now".into(), Some(10))
+            .with_timezone(Some("America/New_York".into()));
+
+        assert_eq!(
+            query.request_body()["options"],
+            serde_json::json!({ "timezone": "America/New_York" })
+        );
+    }
+
+    #[rstest]
+    #[case("*", "abc123", "trace_id:abc123")]
+    #[case("", "7712", "trace_id:7712")]
+    #[case(
+        "service:api OR service:web",
+        "7712",
+        "(service:api OR service:web) trace_id:7712"
+    )]
+    #[case("service:api", "7712", "service:api trace_id:7712")]
+    #[case("*", "a b", r#"trace_id:"a b""#)]
+    #[case("*", r#"x"y\z"#, r#"trace_id:"x\"y\\z""#)]
+    fn test_with_term(#[case] query: &str, #[case] value: &str, #[case] expected: &str) {
+        let query = LogsQuery::new(query.into(), "now-1h".into(), "now".into(), None)
+            .with_term("trace_id", value);
+
+        assert_eq!(query.query, expected);
+    }
+
+    #[test]
+    fn test_collect_facets() {
+        let entries: Vec<LogEntry> = serde_json::from_value(serde_json::json!([
+            {
+                "attributes": {
+                    "service": "api",
+                    "attributes": {
+                        "http": { "status_code": 500, "method": "GET" },
+                        "duration": 42
+                    }
+                }
+            },
+            {
+                "attributes": {
+                    "attributes": { "http": { "status_code": 200 }, "usr": { "id": "u1" }, "empty": {} }
+                }
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            collect_facets(&entries),
+            vec![
+                "duration",
+                "empty",
+                "http.method",
+                "http.status_code",
+                "usr.id"
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("api", "api")]
+    #[case("web-1.prod_east", "web-1.prod_east")]
+    #[case("web api", r#""web api""#)]
+    #[case("db:primary", r#""db:primary""#)]
+    #[case(r#"say "hi""#, r#""say \"hi\"""#)]
+    #[case(r"C:\tmp", r#""C:\\tmp""#)]
+    #[case("(a OR b)", r#""(a OR b)""#)]
+    #[case("", r#""""#)]
+    fn test_escape_query_value(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(escape_query_value(value), expected);
+    }
+
+    #[rstest]
+    #[case("*", &[], "*")]
+    #[case("*", &["api"], "service:api")]
+    #[case("status:error", &["api", "worker"], "status:error service:(api OR worker)")]
+    #[case("a OR b", &["api", "worker"], "(a OR b) service:(api OR worker)")]
+    #[case("env:(prod OR staging)", &["api"], "env:(prod OR staging) service:api")]
+    #[case(r#"message:"a OR b""#, &["api"], r#"message:"a OR b" service:api"#)]
+    #[case("", &["web api", "db:primary"], r#"service:("web api" OR "db:primary")"#)]
+    fn test_and_any_of(#[case] query: &str, #[case] values: &[&str], #[case] expected: &str) {
+        assert_eq!(and_any_of(query, "service", values), expected);
+    }
+
+    #[rstest]
+    #[case("*", &[], "*")]
+    #[case("*", &["healthcheck"], "-service:healthcheck")]
+    #[case("status:error", &["a", "b c"], r#"status:error -service:(a OR "b c")"#)]
+    #[case("a OR b", &["healthcheck"], "(a OR b) -service:healthcheck")]
+    fn test_and_none_of(#[case] query: &str, #[case] values: &[&str], #[case] expected: &str) {
+        assert_eq!(and_none_of(query, "service", values), expected);
+    }
+
+    #[rstest]
+    #[case("datadoghq.com", "https://api.datadoghq.com")]
+    #[case("datadoghq.eu", "https://api.datadoghq.eu")]
+    #[case("us3.datadoghq.com", "https://api.us3.datadoghq.com")]
+    #[case("us5.datadoghq.com", "https://api.us5.datadoghq.com")]
+    #[case("ap1.datadoghq.com", "https://api.ap1.datadoghq.com")]
+    #[case("ddog-gov.com", "https://api.ddog-gov.com")]
+    fn test_endpoint_for_site(#[case] site: &str, #[case] expected_base: &str) {
+        let client = DatadogClient::builder()
+            .api_key("api")
+            .app_key("app")
+            .site(site)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            client.endpoint(LOGS_SEARCH_PATH),
+            format!("{}/api/v2/logs/events/search", expected_base)
+        );
+        assert_eq!(
+            client.endpoint(EVENTS_SEARCH_PATH),
+            format!("{}/api/v2/events", expected_base)
+        );
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let builder = DatadogClient::builder()
+            .api_key("real-api-key")
+            .app_key("real-app-key")
+            .header("X-Proxy-Token", "real-token");
+        let client = builder.clone().build().unwrap();
+
+        for debug in [format!("{:?}", builder), format!("{:?}", client.clone())] {
+            assert!(!debug.contains("real-"), "{}", debug);
+            assert!(debug.contains("***"), "{}", debug);
+            assert!(debug.to_lowercase().contains("x-proxy-token"), "{}", debug);
+        }
+    }
+
+    #[test]
+    fn test_builder_requires_keys() {
+        let result = DatadogClient::builder().app_key("app").build();
+
+        assert!(matches!(result, Err(DatadogError::Config(msg)) if msg.contains("API key")));
+    }
+
+    #[rstest]
+    #[case(None, None, "datadoghq.com", "https://api.datadoghq.com")]
+    #[case(Some("datadoghq.eu"), None, "datadoghq.eu", "https://api.datadoghq.eu")]
+    #[case(
+        Some("datadoghq.eu"),
+        Some("http://localhost:8080/"),
+        "datadoghq.eu",
+        "http://localhost:8080"
+    )]
+    fn test_builder_base_url(
+        #[case] site: Option<&str>,
+        #[case] base_url: Option<&str>,
+        #[case] expected_site: &str,
+        #[case] expected_base_url: &str,
+    ) {
+        let mut builder = DatadogClient::builder()
+            .api_key("api")
+            .app_key("app")
+            .retries(2)
+            .timeout(Duration::from_secs(5));
+        if let Some(site) = site {
+            builder = builder.site(site);
+        }
+        if let Some(base_url) = base_url {
+            builder = builder.base_url(base_url);
+        }
+
+        let client = builder.build().unwrap();
+
+        assert_eq!(client.site(), expected_site);
+        assert_eq!(client.base_url, expected_base_url);
+        assert_eq!(client.retries, 2);
+    }
+
+    #[rstest]
+    #[case("us1", "datadoghq.com")]
+    #[case("eu", "datadoghq.eu")]
+    #[case("EU", "datadoghq.eu")]
+    #[case("us3", "us3.datadoghq.com")]
+    #[case("us5", "us5.datadoghq.com")]
+    #[case("ap1", "ap1.datadoghq.com")]
+    #[case("gov", "ddog-gov.com")]
+    #[case("datadoghq.eu", "datadoghq.eu")]
+    #[case(" https://us5.datadoghq.com/ ", "us5.datadoghq.com")]
+    #[case("custom.example.com", "custom.example.com")]
+    fn test_resolve_site(#[case] site: &str, #[case] expected: &str) {
+        assert_eq!(resolve_site(site), expected);
+    }
+
+    #[test]
+    fn test_builder_resolves_site_alias() {
+        let client = DatadogClient::builder()
+            .api_key("api")
+            .app_key("app")
+            .site("eu")
+            .build()
+            .unwrap();
+
+        assert_eq!(client.site(), "datadoghq.eu");
+        assert_eq!(
+            client.endpoint("/api/v1/validate"),
+            "https://api.datadoghq.eu/api/v1/validate"
+        );
+    }
+
+    #[rstest]
+    #[case(None, 0, 5000)]
+    #[case(None, 123456, 5000)]
+    #[case(Some(100), 100, 0)]
+    #[case(Some(100), 150, 0)]
+    fn test_page_size(#[case] limit: Option<u32>, #[case] fetched: usize, #[case] expected: u32) {
+        assert_eq!(page_size(limit, fetched), expected);
+    }
+
+    #[rstest]
+    #[case(1, 500)]
+    #[case(2, 1000)]
+    #[case(6, 16_000)]
+    #[case(7, 30_000)]
+    #[case(64, 30_000)]
+    #[case(u32::MAX, 30_000)]
+    fn test_backoff_delay_is_capped(#[case] attempt: u32, #[case] expected_ms: u64) {
+        assert_eq!(backoff_delay(attempt), Duration::from_millis(expected_ms));
+    }
+
+    #[rstest]
+    #[case(None, false)]
+    #[case(Some(""), false)]
+    #[case(Some("0"), false)]
+    #[case(Some("1"), true)]
+    #[case(Some("true"), true)]
+    fn test_debug_enabled(#[case] value: Option<&str>, #[case] expected: bool) {
+        assert_eq!(debug_enabled(value), expected);
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::DatadogError;
+use crate::logs::DatadogClient;
+use crate::time::{DisplayTimezone, epoch_millis_to_rfc3339, format_timestamp, resolve_time};
+
+/// Parameters for a metrics timeseries query
+#[derive(Debug, Clone)]
+pub struct MetricsQuery {
+    pub query: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl MetricsQuery {
+    pub fn new(query: String, from: String, to: String) -> Self {
+        Self { query, from, to }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MetricsResponse {
+    pub status: Option<String>,
+    #[serde(default)]
+    pub series: Vec<MetricSeries>,
+    #[serde(flatten)]
+    pub other: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MetricSeries {
+    pub metric: Option<String>,
+    pub scope: Option<String>,
+    pub expression: Option<String>,
+    /// Points as `[timestamp_millis, value]` pairs. Values are null for gaps.
+    #[serde(default)]
+    pub pointlist: Vec<(f64, Option<f64>)>,
+    #[serde(flatten)]
+    pub other: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl DatadogClient {
+    /// Query the metrics timeseries API
+    pub fn query_metrics(&self, query: &MetricsQuery) -> Result<MetricsResponse, DatadogError> {
+        let now = Utc::now();
+        let from = resolve_time(&query.from, now).ok_or_else(|| {
+            DatadogError::Config(format!("Could not resolve start time: {}", query.from))
+        })?;
+        let to = resolve_time(&query.to, now).ok_or_else(|| {
+            DatadogError::Config(format!("Could not resolve end time: {}", query.to))
+        })?;
+
+        let url = self.endpoint(&format!(
+            "/api/v1/query?from={}&to={}&query={}",
+            from.timestamp(),
+            to.timestamp(),
+            urlencoding::encode(&query.query)
+        ));
+        self.get_json(&url)
+    }
+}
+
+pub fn format_metric_series(series: &MetricSeries, tz: DisplayTimezone) -> String {
+    let name = series
+        .expression
+        .as_deref()
+        .or(series.metric.as_deref())
+        .unwrap_or("unknown metric");
+
+    let mut lines = match series.scope.as_deref() {
+        Some(scope) => vec![format!("{} {{{}}}", name, scope)],
+        None => vec![name.to_string()],
+    };
+
+    for (ts, value) in &series.pointlist {
+        let timestamp = epoch_millis_to_rfc3339(*ts as i64);
+        let value = value
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        lines.push(format!(
+            "  {}  {}",
+            format_timestamp(timestamp.as_deref(), tz),
+            value
+        ));
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,84 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::error::DatadogError;
+use crate::format::color_status;
+use crate::logs::DatadogClient;
+
+/// Filters for listing monitors
+#[derive(Debug, Clone, Default)]
+pub struct MonitorsQuery {
+    /// Only include monitors whose name contains this string
+    pub name: Option<String>,
+    /// Only include monitors with all of these tags
+    pub tags: Vec<String>,
+}
+
+impl MonitorsQuery {
+    pub fn new(name: Option<String>, tags: Vec<String>) -> Self {
+        Self { name, tags }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Monitor {
+    pub id: Option<u64>,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub monitor_type: Option<String>,
+    pub query: Option<String>,
+    pub message: Option<String>,
+    pub overall_state: Option<String>,
+    pub tags: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub other: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl DatadogClient {
+    /// Fetch a single monitor by its numeric ID.
+    pub fn get_monitor(&self, id: u64) -> Result<Monitor, DatadogError> {
+        let url = self.endpoint(&format!("/api/v1/monitor/{}", id));
+        self.get_json(&url)
+    }
+
+    /// List monitors, optionally filtered by name and tags.
+    pub fn list_monitors(&self, query: &MonitorsQuery) -> Result<Vec<Monitor>, DatadogError> {
+        let mut url = self.endpoint("/api/v1/monitor");
+
+        let mut params = Vec::new();
+        if let Some(name) = &query.name {
+            params.push(format!("name={}", urlencoding::encode(name)));
+        }
+        if !query.tags.is_empty() {
+            params.push(format!(
+                "monitor_tags={}",
+                urlencoding::encode(&query.tags.join(","))
+            ));
+        }
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&params.join("&"));
+        }
+
+        self.get_json(&url)
+    }
+}
+
+pub fn format_monitor(monitor: &Monitor) -> String {
+    let id = monitor
+        .id
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    let state_raw = monitor
+        .overall_state
+        .as_ref()
+        .map(|s| s.to_uppercase())
+        .unwrap_or_else(|| "-----".to_string());
+
+    let state_colored = color_status(&format!("{:5}", state_raw));
+
+    let name = monitor.name.as_deref().unwrap_or("Unnamed Monitor");
+
+    format!("[{}] {} | {}", id.bright_black(), state_colored, name)
+}
@@ -0,0 +1,101 @@
+/// Uniform random sample of at most `size` items from a stream of unknown length,
+/// using reservoir sampling (Algorithm R). Only the sample is held in memory.
+#[derive(Debug, Clone)]
+pub struct Reservoir<T> {
+    size: usize,
+    seen: usize,
+    /// Sampled items with their position in the stream
+    items: Vec<(usize, T)>,
+    rng: fastrand::Rng,
+}
+
+impl<T> Reservoir<T> {
+    /// A reservoir of `size` items. The same `seed` over the same stream picks the same sample.
+    pub fn new(size: usize, seed: Option<u64>) -> Self {
+        Self {
+            size,
+            seen: 0,
+            items: Vec::with_capacity(size),
+            rng: seed.map_or_else(fastrand::Rng::new, fastrand::Rng::with_seed),
+        }
+    }
+
+    /// Offer the next item of the stream
+    pub fn push(&mut self, item: T) {
+        if self.items.len() < self.size {
+            self.items.push((self.seen, item));
+        } else {
+            // Keep the i-th item with probability size/(i+1), replacing a random one
+            let slot = self.rng.usize(..=self.seen);
+            if slot < self.size {
+                self.items[slot] = (self.seen, item);
+            }
+        }
+        self.seen += 1;
+    }
+
+    /// Number of items offered so far
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// The sampled items, in the order they appeared in the stream
+    pub fn into_items(mut self) -> Vec<T> {
+        self.items.sort_unstable_by_key(|(index, _)| *index);
+        self.items.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+impl<T> Extend<T> for Reservoir<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn sample(size: usize, len: usize, seed: u64) -> Vec<usize> {
+        let mut reservoir = Reservoir::new(size, Some(seed));
+        reservoir.extend(0..len);
+        assert_eq!(reservoir.seen(), len);
+        reservoir.into_items()
+    }
+
+    #[rstest]
+    #[case(5, 3, 3)]
+    #[case(5, 5, 5)]
+    #[case(5, 1000, 5)]
+    #[case(1, 1000, 1)]
+    fn test_sample_size(#[case] size: usize, #[case] len: usize, #[case] expected: usize) {
+        let items = sample(size, len, 7);
+        assert_eq!(items.len(), expected);
+        assert!(items.windows(2).all(|w| w[0] < w[1]), "{:?}", items);
+    }
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        assert_eq!(sample(10, 1000, 42), sample(10, 1000, 42));
+        assert_ne!(sample(10, 1000, 42), sample(10, 1000, 43));
+    }
+
+    #[test]
+    fn test_sample_is_roughly_uniform() {
+        // Each of 10 items is kept with probability 1/2 over 2000 runs
+        let mut counts = [0; 10];
+        for seed in 0..2000 {
+            for item in sample(5, 10, seed) {
+                counts[item] += 1;
+            }
+        }
+        assert!(
+            counts.iter().all(|&c| (800..1200).contains(&c)),
+            "{:?}",
+            counts
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::logs::LogEntry;
+use crate::time::{DisplayTimezone, format_timestamp};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// A user-supplied output template such as `{timestamp} {status} {message}`.
+///
+/// Placeholders are replaced with the matching log field, or with any flattened
+/// attribute key. Missing keys render as an empty string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogTemplate {
+    segments: Vec<Segment>,
+}
+
+impl std::str::FromStr for LogTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.char_indices();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        match c {
+                            '}' => {
+                                closed = true;
+                                break;
+                            }
+                            '{' => {
+                                return Err(format!(
+                                    "Invalid template: nested '{{' in placeholder starting at position {}",
+                                    pos
+                                ));
+                            }
+                            _ => name.push(c),
+                        }
+                    }
+
+                    if !closed {
+                        return Err(format!(
+                            "Invalid template: unclosed '{{' at position {}",
+                            pos
+                        ));
+                    }
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(format!(
+                            "Invalid template: empty placeholder at position {}",
+                            pos
+                        ));
+                    }
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(name.to_string()));
+                }
+                '}' => {
+                    return Err(format!(
+                        "Invalid template: unmatched '}}' at position {}",
+                        pos
+                    ));
+                }
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl LogTemplate {
+    /// Render a log entry using this template. Output is never colored.
+    pub fn render(&self, entry: &LogEntry, tz: DisplayTimezone) -> String {
+        let mut out = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Placeholder(name) => out.push_str(&lookup_field(entry, name, tz)),
+            }
+        }
+
+        out
+    }
+}
+
+/// Render a named log field, or any attribute key or dotted path. Missing keys render as "".
+pub(crate) fn lookup_field(entry: &LogEntry, name: &str, tz: DisplayTimezone) -> String {
+    let attrs = &entry.attributes;
+
+    match name {
+        "timestamp" => format_timestamp(attrs.timestamp.as_deref(), tz),
+        "status" => attrs.status.clone().unwrap_or_default(),
+        "message" => attrs.message.clone().unwrap_or_default(),
+        "host" => attrs.host.clone().unwrap_or_default(),
+        "service" => attrs.service.clone().unwrap_or_default(),
+        "tags" => attrs.tags.as_ref().map(|t| t.join(",")).unwrap_or_default(),
+        "id" => entry.id.clone().unwrap_or_default(),
+        _ => attrs
+            .get_path(name)
+            .map(|value| match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn sample_entry() -> LogEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": "abc",
+            "type": "log",
+            "attributes": {
+                "timestamp": "2024-01-01T12:00:00Z",
+                "status": "error",
+                "message": "boom",
+                "service": "api",
+                "host": "web-1",
+                "attributes": {
+                    "duration": 42,
+                    "env": "prod"
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case("{timestamp} {status} {message}", "2024-01-01 12:00:00 error boom")]
+    #[case("[{service}@{host}] {message}", "[api@web-1] boom")]
+    #[case("{env} took {duration}ms", "prod took 42ms")]
+    #[case("{missing}|{message}", "|boom")]
+    #[case("plain text", "plain text")]
+    fn test_render_template(#[case] template: &str, #[case] expected: &str) {
+        let template: LogTemplate = template.parse().unwrap();
+
+        assert_eq!(
+            template.render(&sample_entry(), DisplayTimezone::Utc),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("{message", "unclosed")]
+    #[case("message}", "unmatched")]
+    #[case("{mes{sage}}", "nested")]
+    #[case("{}", "empty placeholder")]
+    fn test_reject_unbalanced_template(#[case] template: &str, #[case] error_contains: &str) {
+        let result = template.parse::<LogTemplate>();
+
+        assert!(result.unwrap_err().contains(error_contains));
+    }
+}
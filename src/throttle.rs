@@ -0,0 +1,71 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Spaces requests at least `1 / rate` seconds apart, shared by every thread using a
+/// client. A token bucket holding a single token, so there are no bursts.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// Earliest instant the next request may start
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Allow at most `per_second` requests per second
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Reserve the next slot as of `now`, returning how long to wait before using it
+    pub fn reserve(&self, now: Instant) -> Duration {
+        let mut next = self.next.lock().unwrap();
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + self.interval);
+        slot - now
+    }
+
+    /// Block until a request may be sent
+    pub fn wait(&self) {
+        let delay = self.reserve(Instant::now());
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_spaces_requests() {
+        let limiter = RateLimiter::new(4);
+        let start = Instant::now();
+
+        let delays: Vec<Duration> = (0..3).map(|_| limiter.reserve(start)).collect();
+
+        assert_eq!(
+            delays,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_millis(500)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_idle_time_does_not_allow_bursts() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        limiter.reserve(start);
+
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(later), Duration::ZERO);
+        assert_eq!(limiter.reserve(later), Duration::from_millis(500));
+    }
+}
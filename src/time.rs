@@ -0,0 +1,533 @@
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Timezone used when displaying timestamps
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DisplayTimezone {
+    #[default]
+    Utc,
+    Local,
+    Named(Tz),
+}
+
+impl std::str::FromStr for DisplayTimezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utc" => Ok(DisplayTimezone::Utc),
+            "local" => Ok(DisplayTimezone::Local),
+            _ => s.parse::<Tz>().map(DisplayTimezone::Named).map_err(|_| {
+                format!(
+                    "Invalid timezone: {} (expected 'local', 'UTC', or an IANA name like America/New_York)",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+impl DisplayTimezone {
+    /// The timezone as the API's `options.timezone` expects it, e.g. `America/New_York`
+    /// or `UTC+5:30` for a local offset. None for UTC, the API's default.
+    pub fn api_timezone(&self) -> Option<String> {
+        match self {
+            DisplayTimezone::Utc => None,
+            DisplayTimezone::Named(tz) => Some(tz.name().to_string()),
+            DisplayTimezone::Local => {
+                let offset = Local::now().offset().local_minus_utc();
+                Some(format_utc_offset(offset))
+            }
+        }
+    }
+}
+
+/// A UTC offset in seconds as `UTC+1`, `UTC-3:30` or `UTC`
+fn format_utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.unsigned_abs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => "UTC".to_string(),
+        (hours, 0) => format!("UTC{}{}", sign, hours),
+        (hours, minutes) => format!("UTC{}{}:{:02}", sign, hours, minutes),
+    }
+}
+
+/// How displayed timestamps are rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimeFormat {
+    /// `%Y-%m-%d %H:%M:%S` in the display timezone
+    #[default]
+    Absolute,
+    /// Age at print time, e.g. `2m ago`
+    Relative,
+    /// The timestamp as returned by the API (RFC3339)
+    Iso,
+}
+
+impl std::str::FromStr for TimeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "absolute" => Ok(TimeFormat::Absolute),
+            "relative" => Ok(TimeFormat::Relative),
+            "iso" => Ok(TimeFormat::Iso),
+            _ => Err(format!(
+                "Invalid time format: {} (expected absolute, relative or iso)",
+                s
+            )),
+        }
+    }
+}
+
+/// Format an RFC3339 timestamp for display as `format`. Relative ages are measured from
+/// `now`; the timezone only applies to absolute times.
+pub fn display_timestamp(
+    timestamp: Option<&str>,
+    tz: DisplayTimezone,
+    format: TimeFormat,
+    now: DateTime<Utc>,
+) -> String {
+    match format {
+        TimeFormat::Absolute => format_timestamp(timestamp, tz),
+        TimeFormat::Iso => timestamp
+            .filter(|ts| DateTime::parse_from_rfc3339(ts).is_ok())
+            .map(str::to_string)
+            .unwrap_or_else(|| format_timestamp(None, tz)),
+        TimeFormat::Relative => timestamp
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|dt| format_age(now.signed_duration_since(dt)))
+            .unwrap_or_else(|| format_timestamp(None, tz)),
+    }
+}
+
+/// A duration as an age in its largest whole unit, e.g. `90s` as `1m ago`. Times in the
+/// future count as `0s ago`.
+fn format_age(age: chrono::Duration) -> String {
+    let seconds = age.num_seconds().max(0);
+    let (amount, unit) = match seconds {
+        0..60 => (seconds, 's'),
+        60..3_600 => (seconds / 60, 'm'),
+        3_600..86_400 => (seconds / 3_600, 'h'),
+        _ => (seconds / 86_400, 'd'),
+    };
+    format!("{}{} ago", amount, unit)
+}
+
+/// Format an RFC3339 timestamp for display in the given timezone
+pub fn format_timestamp(timestamp: Option<&str>, tz: DisplayTimezone) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+    timestamp
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|dt| match tz {
+            DisplayTimezone::Utc => dt.with_timezone(&Utc).format(FORMAT).to_string(),
+            DisplayTimezone::Local => dt.with_timezone(&Local).format(FORMAT).to_string(),
+            DisplayTimezone::Named(tz) => dt.with_timezone(&tz).format(FORMAT).to_string(),
+        })
+        .unwrap_or_else(|| "--------------------".to_string())
+}
+
+/// Convert an epoch-millisecond timestamp to RFC3339
+pub fn epoch_millis_to_rfc3339(ms: i64) -> Option<String> {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .map(|dt| dt.to_rfc3339())
+}
+
+/// Whether a value looks like an epoch-millisecond timestamp (13 digits)
+fn looks_like_epoch_millis(value: &str) -> bool {
+    value.len() == 13 && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Validate a time expression and return it in the form sent to the Datadog API.
+///
+/// Accepted forms:
+/// - `now`
+/// - `now-<N><unit>` where unit is one of `s`, `m`, `h`, `d`, `w`
+/// - `-<N><unit>` as shorthand for `now-<N><unit>`
+/// - RFC3339 timestamps (e.g. `2024-01-01T00:00:00Z`)
+/// - Epoch-millisecond timestamps (13 digits), converted to RFC3339
+/// - Other epoch timestamps (all digits), read as seconds and converted to RFC3339, since
+///   the API would read them as milliseconds
+/// - English phrases: `N <unit> ago` (e.g. `2 days ago`), converted to `now-<N><unit>`,
+///   and `today`/`yesterday`, converted to the start of that day in UTC
+pub fn parse_time(input: &str) -> Result<String, String> {
+    let value = input.trim();
+
+    if value.is_empty() {
+        return Err("Time expression cannot be empty".to_string());
+    }
+
+    if let Some(parsed) = parse_phrase(value, Utc::now()) {
+        return Ok(parsed);
+    }
+
+    if value == "now" {
+        return Ok("now".to_string());
+    }
+
+    // Shorthand: "-15m" implies "now-15m"
+    let relative = value
+        .strip_prefix("now-")
+        .or_else(|| value.strip_prefix('-'));
+
+    if let Some(offset) = relative {
+        return parse_offset(offset)
+            .map(|(amount, unit)| format!("now-{}{}", amount, unit))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid relative time: {} (expected now-<N><unit> with unit s, m, h, d or w)",
+                    input
+                )
+            });
+    }
+
+    if looks_like_epoch_millis(value)
+        && let Some(rfc3339) = value.parse().ok().and_then(epoch_millis_to_rfc3339)
+    {
+        return Ok(rfc3339);
+    }
+
+    if value.chars().all(|c| c.is_ascii_digit()) {
+        return value
+            .parse()
+            .ok()
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+            .map(|dt| dt.to_rfc3339())
+            .ok_or_else(|| format!("Invalid time: {} (epoch seconds out of range)", input));
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.to_rfc3339())
+        .map_err(|_| {
+            format!(
+                "Invalid time: {} (expected now, now-<N><unit>, an RFC3339 timestamp or an epoch timestamp)",
+                input
+            )
+        })
+}
+
+/// Convert an English time phrase to the form `parse_time` returns, or None if `value`
+/// isn't one
+fn parse_phrase(value: &str, now: DateTime<Utc>) -> Option<String> {
+    let value = value.to_lowercase();
+    let words: Vec<&str> = value.split_whitespace().collect();
+
+    let days_back = match words.as_slice() {
+        ["today"] => 0,
+        ["yesterday"] => 1,
+        [amount, unit, "ago"] => {
+            let amount: u64 = match *amount {
+                "a" | "an" => 1,
+                amount => amount.parse().ok()?,
+            };
+            let unit = match unit.strip_suffix('s').unwrap_or(unit) {
+                "second" | "sec" => 's',
+                "minute" | "min" => 'm',
+                "hour" => 'h',
+                "day" => 'd',
+                "week" => 'w',
+                _ => return None,
+            };
+            return Some(format!("now-{}{}", amount, unit));
+        }
+        _ => return None,
+    };
+
+    let midnight = now.date_naive().and_hms_opt(0, 0, 0)?.and_utc();
+    let start = midnight.checked_sub_signed(chrono::Duration::days(days_back))?;
+    Some(start.to_rfc3339())
+}
+
+/// Resolve a time expression (as accepted by `parse_time`) to an absolute instant,
+/// relative to `now`. Returns None if the expression isn't recognized.
+pub fn resolve_time(input: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let value = parse_time(input).ok()?;
+
+    if value == "now" {
+        return Some(now);
+    }
+
+    if let Some(offset) = value.strip_prefix("now-") {
+        let (amount, unit) = parse_offset(offset)?;
+        let amount = i64::try_from(amount).ok()?;
+        let duration = match unit {
+            's' => chrono::Duration::try_seconds(amount),
+            'm' => chrono::Duration::try_minutes(amount),
+            'h' => chrono::Duration::try_hours(amount),
+            'd' => chrono::Duration::try_days(amount),
+            'w' => chrono::Duration::try_weeks(amount),
+            _ => None,
+        }?;
+        return now.checked_sub_signed(duration);
+    }
+
+    DateTime::parse_from_rfc3339(&value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Check that `from` is not after `to`, resolving both relative to `now`.
+/// Ranges where either side can't be resolved are allowed through.
+pub fn validate_time_range(from: &str, to: &str, now: DateTime<Utc>) -> Result<(), String> {
+    match (resolve_time(from, now), resolve_time(to, now)) {
+        (Some(start), Some(end)) if start > end => Err(format!(
+            "start time is after end time (from {} to {})",
+            from, to
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Parse a duration such as `30s`, `5m` or `2h` (units s, m, h, d or w). A bare number
+/// is seconds.
+pub fn parse_duration(input: &str) -> Result<std::time::Duration, String> {
+    let value = input.trim();
+    let seconds = match value.parse::<u64>() {
+        Ok(seconds) => Some(seconds),
+        Err(_) => parse_offset(value).and_then(|(amount, unit)| {
+            let unit_seconds = match unit {
+                's' => 1,
+                'm' => 60,
+                'h' => 60 * 60,
+                'd' => 24 * 60 * 60,
+                _ => 7 * 24 * 60 * 60,
+            };
+            amount.checked_mul(unit_seconds)
+        }),
+    };
+
+    seconds.map(std::time::Duration::from_secs).ok_or_else(|| {
+        format!(
+            "Invalid duration: {} (expected <N><unit> with unit s, m, h, d or w)",
+            input
+        )
+    })
+}
+
+/// Split an offset like `15m` into its amount and unit
+fn parse_offset(offset: &str) -> Option<(u64, char)> {
+    let unit = offset.chars().last()?;
+    if !matches!(unit, 's' | 'm' | 'h' | 'd' | 'w') {
+        return None;
+    }
+
+    let amount = &offset[..offset.len() - 1];
+    if amount.is_empty() || !amount.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    amount.parse().ok().map(|n| (n, unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("now", "now")]
+    #[case("now-15m", "now-15m")]
+    #[case("now-30s", "now-30s")]
+    #[case("now-2h", "now-2h")]
+    #[case("now-7d", "now-7d")]
+    #[case("now-1w", "now-1w")]
+    #[case("-15m", "now-15m")]
+    #[case(" now-1h ", "now-1h")]
+    #[case("1704067200000", "2024-01-01T00:00:00+00:00")]
+    #[case("1704067200", "2024-01-01T00:00:00+00:00")]
+    #[case("2024-01-01T00:00:00Z", "2024-01-01T00:00:00+00:00")]
+    fn test_parse_valid_time(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(parse_time(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("1 hour ago", Some("now-1h"))]
+    #[case("2 days ago", Some("now-2d"))]
+    #[case("30 seconds ago", Some("now-30s"))]
+    #[case("1 second ago", Some("now-1s"))]
+    #[case("15 minutes ago", Some("now-15m"))]
+    #[case("5 mins ago", Some("now-5m"))]
+    #[case("3 Weeks Ago", Some("now-3w"))]
+    #[case("an hour ago", Some("now-1h"))]
+    #[case("a day ago", Some("now-1d"))]
+    #[case("today", Some("2024-01-01T00:00:00+00:00"))]
+    #[case("Yesterday", Some("2023-12-31T00:00:00+00:00"))]
+    #[case("2 years ago", None)]
+    #[case("hour ago", None)]
+    #[case("now-1h", None)]
+    fn test_parse_phrase(#[case] input: &str, #[case] expected: Option<&str>) {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(parse_phrase(input, now).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_parse_time_accepts_phrases() {
+        assert_eq!(parse_time(" 1 hour ago ").unwrap(), "now-1h");
+    }
+
+    #[rstest]
+    #[case("", "cannot be empty")]
+    #[case("now-15mm", "Invalid relative time")]
+    #[case("now-m", "Invalid relative time")]
+    #[case("now-15y", "Invalid relative time")]
+    #[case("-", "Invalid relative time")]
+    #[case("tomorrow", "Invalid time")]
+    #[case("2 fortnights ago", "Invalid time")]
+    #[case("2024-01-01", "Invalid time")]
+    #[case("99999999999999999999", "out of range")]
+    fn test_reject_invalid_time(#[case] input: &str, #[case] error_contains: &str) {
+        let result = parse_time(input);
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains(error_contains));
+    }
+
+    #[rstest]
+    #[case("now", "2024-01-01T12:00:00+00:00")]
+    #[case("now-15m", "2024-01-01T11:45:00+00:00")]
+    #[case("-2h", "2024-01-01T10:00:00+00:00")]
+    #[case("now-1w", "2023-12-25T12:00:00+00:00")]
+    #[case("1704067200000", "2024-01-01T00:00:00+00:00")]
+    #[case("1704067200", "2024-01-01T00:00:00+00:00")]
+    #[case("2024-01-01T06:00:00-05:00", "2024-01-01T11:00:00+00:00")]
+    fn test_resolve_time(#[case] input: &str, #[case] expected: &str) {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(resolve_time(input, now).unwrap().to_rfc3339(), expected);
+    }
+
+    #[rstest]
+    #[case("30", Some(30))]
+    #[case("30s", Some(30))]
+    #[case("5m", Some(300))]
+    #[case(" 2h ", Some(7200))]
+    #[case("1w", Some(604800))]
+    #[case("5x", None)]
+    #[case("-5m", None)]
+    #[case("", None)]
+    fn test_parse_duration(#[case] input: &str, #[case] expected_secs: Option<u64>) {
+        let result = parse_duration(input);
+
+        assert_eq!(result.ok().map(|d| d.as_secs()), expected_secs);
+    }
+
+    #[rstest]
+    #[case("now-1h", "now", true)]
+    #[case("now", "now-1h", false)]
+    #[case("-15m", "-1h", false)]
+    #[case("2024-01-01T00:00:00Z", "2024-01-01T00:00:00Z", true)]
+    #[case("2024-01-02T00:00:00Z", "1704067200000", false)]
+    #[case("not a time", "now-1h", true)]
+    fn test_validate_time_range(#[case] from: &str, #[case] to: &str, #[case] valid: bool) {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let result = validate_time_range(from, to, now);
+
+        assert_eq!(result.is_ok(), valid, "{:?}", result);
+        if let Err(e) = result {
+            assert!(e.contains("start time is after end time"));
+        }
+    }
+
+    #[rstest]
+    #[case("UTC", DisplayTimezone::Utc)]
+    #[case("utc", DisplayTimezone::Utc)]
+    #[case("local", DisplayTimezone::Local)]
+    #[case(
+        "America/New_York",
+        DisplayTimezone::Named(chrono_tz::America::New_York)
+    )]
+    fn test_parse_timezone(#[case] input: &str, #[case] expected: DisplayTimezone) {
+        assert_eq!(input.parse::<DisplayTimezone>().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("Mars/Olympus_Mons")]
+    #[case("Not_A_Zone")]
+    fn test_reject_invalid_timezone(#[case] input: &str) {
+        let result = input.parse::<DisplayTimezone>();
+
+        assert!(result.unwrap_err().contains("IANA name"));
+    }
+
+    #[rstest]
+    #[case(
+        Some("2024-01-01T12:00:00Z"),
+        DisplayTimezone::Utc,
+        "2024-01-01 12:00:00"
+    )]
+    #[case(
+        Some("2024-01-01T12:00:00Z"),
+        DisplayTimezone::Named(chrono_tz::America::New_York),
+        "2024-01-01 07:00:00"
+    )]
+    #[case(None, DisplayTimezone::Utc, "--------------------")]
+    #[case(Some("garbage"), DisplayTimezone::Utc, "--------------------")]
+    fn test_format_timestamp(
+        #[case] timestamp: Option<&str>,
+        #[case] tz: DisplayTimezone,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(format_timestamp(timestamp, tz), expected);
+    }
+
+    #[rstest]
+    #[case(0, "UTC")]
+    #[case(3_600, "UTC+1")]
+    #[case(-12_600, "UTC-3:30")]
+    #[case(20_700, "UTC+5:45")]
+    fn test_format_utc_offset(#[case] seconds: i32, #[case] expected: &str) {
+        assert_eq!(format_utc_offset(seconds), expected);
+    }
+
+    #[rstest]
+    #[case(DisplayTimezone::Utc, None)]
+    #[case(
+        DisplayTimezone::Named(chrono_tz::America::New_York),
+        Some("America/New_York")
+    )]
+    fn test_api_timezone(#[case] tz: DisplayTimezone, #[case] expected: Option<&str>) {
+        assert_eq!(tz.api_timezone().as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(Some("2024-01-01T11:59:30Z"), TimeFormat::Relative, "30s ago")]
+    #[case(Some("2024-01-01T11:58:00Z"), TimeFormat::Relative, "2m ago")]
+    #[case(Some("2024-01-01T09:00:00+00:00"), TimeFormat::Relative, "3h ago")]
+    #[case(Some("2023-12-30T12:00:00Z"), TimeFormat::Relative, "2d ago")]
+    #[case(Some("2024-01-01T12:05:00Z"), TimeFormat::Relative, "0s ago")]
+    #[case(
+        Some("2024-01-01T11:58:00.123Z"),
+        TimeFormat::Iso,
+        "2024-01-01T11:58:00.123Z"
+    )]
+    #[case(
+        Some("2024-01-01T11:58:00Z"),
+        TimeFormat::Absolute,
+        "2024-01-01 11:58:00"
+    )]
+    #[case(None, TimeFormat::Relative, "--------------------")]
+    #[case(Some("garbage"), TimeFormat::Iso, "--------------------")]
+    fn test_display_timestamp(
+        #[case] timestamp: Option<&str>,
+        #[case] format: TimeFormat,
+        #[case] expected: &str,
+    ) {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            display_timestamp(timestamp, DisplayTimezone::Utc, format, now),
+            expected
+        );
+    }
+}
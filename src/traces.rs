@@ -0,0 +1,20 @@
+/// Parameters for an APM trace search query
+#[derive(Debug, Clone)]
+pub struct TracesQuery {
+    pub query: String,
+    pub from: String,
+    pub to: String,
+    /// Maximum number of spans to retrieve. None = fetch all.
+    pub limit: Option<u32>,
+}
+
+impl TracesQuery {
+    pub fn new(query: String, from: String, to: String, limit: Option<u32>) -> Self {
+        Self {
+            query,
+            from,
+            to,
+            limit,
+        }
+    }
+}
@@ -1,13 +1,25 @@
-use chrono::{TimeZone, Utc};
 use url::Url;
 
 use crate::events::EventsQuery;
-use crate::logs::LogsQuery;
+use crate::logs::{LogsQuery, site_for_host};
+use crate::time::epoch_millis_to_rfc3339;
+use crate::traces::TracesQuery;
 
+/// What a Datadog web app URL points at, and the site it belongs to
 #[derive(Debug)]
-pub enum DatadogResource {
+pub struct DatadogResource {
+    /// The Datadog site of the URL's host, e.g. `datadoghq.eu`
+    pub site: &'static str,
+    pub kind: ResourceKind,
+}
+
+#[derive(Debug)]
+pub enum ResourceKind {
     Logs(LogsQuery),
     Events(EventsQuery),
+    Monitor { id: u64 },
+    Dashboard { id: String },
+    Trace(TracesQuery),
 }
 
 pub fn parse_datadog_url(url_str: &str) -> Result<DatadogResource, String> {
@@ -15,9 +27,12 @@ pub fn parse_datadog_url(url_str: &str) -> Result<DatadogResource, String> {
 
     // Verify it's a Datadog URL
     let host = parsed.host_str().unwrap_or("");
-    if !host.contains("datadoghq.com") {
-        return Err("URL must be a Datadog URL (*.datadoghq.com)".to_string());
-    }
+    let Some(site) = site_for_host(host) else {
+        return Err(
+            "URL must be a Datadog URL (e.g. app.datadoghq.com, app.datadoghq.eu or app.ddog-gov.com)"
+                .to_string(),
+        );
+    };
 
     let path = parsed.path();
 
@@ -28,23 +43,13 @@ pub fn parse_datadog_url(url_str: &str) -> Result<DatadogResource, String> {
     let from = params
         .get("from_ts")
         .and_then(|ts| ts.parse::<i64>().ok())
-        .map(|ms| {
-            Utc.timestamp_millis_opt(ms)
-                .single()
-                .map(|dt| dt.to_rfc3339())
-                .unwrap_or_else(|| "now-15m".to_string())
-        })
+        .and_then(epoch_millis_to_rfc3339)
         .unwrap_or_else(|| "now-15m".to_string());
 
     let to = params
         .get("to_ts")
         .and_then(|ts| ts.parse::<i64>().ok())
-        .map(|ms| {
-            Utc.timestamp_millis_opt(ms)
-                .single()
-                .map(|dt| dt.to_rfc3339())
-                .unwrap_or_else(|| "now".to_string())
-        })
+        .and_then(epoch_millis_to_rfc3339)
         .unwrap_or_else(|| "now".to_string());
 
     let query = params
@@ -52,26 +57,103 @@ pub fn parse_datadog_url(url_str: &str) -> Result<DatadogResource, String> {
         .map(|s| s.to_string())
         .unwrap_or_else(|| "*".to_string());
 
-    match path {
-        "/logs" => Ok(DatadogResource::Logs(LogsQuery::new(
-            query,
-            from,
-            to,
-            Some(100),
-        ))),
-        "/event/explorer" => Ok(DatadogResource::Events(EventsQuery::new(
-            query,
-            from,
-            to,
-            Some(100),
-        ))),
-        _ => Err(format!(
-            "Unsupported Datadog resource: {}. Currently only /logs and /event/explorer are supported.",
-            path
-        )),
+    let kind = match path {
+        "/logs" => {
+            let saved_view = params
+                .get("saved-view-id")
+                .or_else(|| params.get("saved_view"))
+                .map(|id| id.to_string())
+                .filter(|id| !id.is_empty());
+            ResourceKind::Logs(
+                LogsQuery::new(query, from, to, Some(100)).with_saved_view(saved_view),
+            )
+        }
+        "/event/explorer" => {
+            let query = with_service_facet(query, params.get("service").map(|s| s.as_ref()));
+            let sort = params.get("sort").and_then(|s| parse_event_sort(s));
+            ResourceKind::Events(EventsQuery::new(query, from, to, Some(100)).with_sort(sort))
+        }
+        "/apm/traces" => ResourceKind::Trace(TracesQuery::new(query, from, to, Some(100))),
+        p if p == "/monitors" || p.starts_with("/monitors/") => {
+            let id = parse_monitor_id(p)?;
+            ResourceKind::Monitor { id }
+        }
+        p if p == "/dashboard" || p.starts_with("/dashboard/") => {
+            let id = parse_dashboard_id(p)?;
+            ResourceKind::Dashboard { id }
+        }
+        _ => {
+            return Err(format!(
+                "Unsupported Datadog resource: {}. Currently only /logs, /event/explorer, /apm/traces, /monitors/{{id}} and /dashboard/{{id}} are supported.",
+                path
+            ));
+        }
+    };
+
+    Ok(DatadogResource { site, kind })
+}
+
+/// Merge a `service` facet selection into the search query, unless the query already filters on it
+fn with_service_facet(query: String, service: Option<&str>) -> String {
+    let Some(service) = service.map(str::trim).filter(|s| !s.is_empty()) else {
+        return query;
+    };
+
+    let facet = format!("service:{}", service);
+    if query.split_whitespace().any(|term| term == facet) {
+        query
+    } else if query == "*" {
+        facet
+    } else {
+        format!("{} {}", query, facet)
     }
 }
 
+/// Map an explorer `sort` param to the events API sort value. Unknown values are ignored.
+fn parse_event_sort(sort: &str) -> Option<String> {
+    match sort {
+        "asc" | "timestamp" => Some("timestamp".to_string()),
+        "desc" | "-timestamp" => Some("-timestamp".to_string()),
+        _ => None,
+    }
+}
+
+/// Extract the dashboard ID from a `/dashboard/{id}[/{slug}]` path
+fn parse_dashboard_id(path: &str) -> Result<String, String> {
+    let segment = path
+        .trim_start_matches("/dashboard")
+        .trim_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or("");
+
+    if segment.is_empty() {
+        return Err(
+            "Dashboard URL is missing a dashboard ID (expected /dashboard/{id})".to_string(),
+        );
+    }
+
+    Ok(segment.to_string())
+}
+
+/// Extract the numeric monitor ID from a `/monitors/{id}` path
+fn parse_monitor_id(path: &str) -> Result<u64, String> {
+    let segment = path
+        .trim_start_matches("/monitors")
+        .trim_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or("");
+
+    if segment.is_empty() {
+        return Err("Monitor URL is missing a monitor ID (expected /monitors/{id})".to_string());
+    }
+
+    segment
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid monitor ID: {} (expected a number)", segment))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,6 +173,16 @@ mod tests {
         "now-15m",
         "now"
     )]
+    #[case(
+        "https://app.datadoghq.eu/logs?query=env%3Aprod",
+        "env:prod",
+        "now-15m",
+        "now"
+    )]
+    #[case("https://us3.datadoghq.com/logs", "*", "now-15m", "now")]
+    #[case("https://ap1.datadoghq.com/logs", "*", "now-15m", "now")]
+    #[case("https://app.ddog-gov.com/logs", "*", "now-15m", "now")]
+    #[case("https://myorg.datadoghq.eu/logs", "*", "now-15m", "now")]
     fn test_parse_valid_logs_url(
         #[case] url: &str,
         #[case] expected_query: &str,
@@ -99,8 +191,8 @@ mod tests {
     ) {
         let result = parse_datadog_url(url).expect("should parse successfully");
 
-        match result {
-            DatadogResource::Logs(query) => {
+        match result.kind {
+            ResourceKind::Logs(query) => {
                 assert_eq!(query.query, expected_query);
                 assert_eq!(query.from, expected_from);
                 assert_eq!(query.to, expected_to);
@@ -110,6 +202,22 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case(
+        "https://app.datadoghq.com/logs?query=service%3Aweb&saved-view-id=12345",
+        Some("12345")
+    )]
+    #[case("https://app.datadoghq.com/logs?saved_view=abc", Some("abc"))]
+    #[case("https://app.datadoghq.com/logs?query=service%3Aweb", None)]
+    fn test_parse_logs_url_with_saved_view(#[case] url: &str, #[case] expected: Option<&str>) {
+        let result = parse_datadog_url(url).expect("should parse successfully");
+
+        match result.kind {
+            ResourceKind::Logs(query) => assert_eq!(query.saved_view.as_deref(), expected),
+            _ => panic!("Expected Logs resource"),
+        }
+    }
+
     #[rstest]
     #[case(
         "https://app.datadoghq.com/logs?query=*&from_ts=1704067200000&to_ts=1704153600000",
@@ -125,8 +233,8 @@ mod tests {
     ) {
         let result = parse_datadog_url(url).expect("should parse successfully");
 
-        match result {
-            DatadogResource::Logs(query) => {
+        match result.kind {
+            ResourceKind::Logs(query) => {
                 assert_eq!(query.query, expected_query);
                 assert!(query.from.contains(from_contains));
                 assert!(query.to.contains(to_contains));
@@ -157,8 +265,8 @@ mod tests {
     ) {
         let result = parse_datadog_url(url).expect("should parse successfully");
 
-        match result {
-            DatadogResource::Events(query) => {
+        match result.kind {
+            ResourceKind::Events(query) => {
                 assert_eq!(query.query, expected_query);
                 assert_eq!(query.from, expected_from);
                 assert_eq!(query.to, expected_to);
@@ -183,8 +291,8 @@ mod tests {
     ) {
         let result = parse_datadog_url(url).expect("should parse successfully");
 
-        match result {
-            DatadogResource::Events(query) => {
+        match result.kind {
+            ResourceKind::Events(query) => {
                 assert_eq!(query.query, expected_query);
                 assert!(query.from.contains(from_contains));
                 assert!(query.to.contains(to_contains));
@@ -193,11 +301,143 @@ mod tests {
         }
     }
 
+    #[rstest]
+    #[case(
+        "https://app.datadoghq.com/event/explorer?query=runner&sort=desc&cols=title",
+        "runner",
+        Some("-timestamp")
+    )]
+    #[case(
+        "https://app.datadoghq.com/event/explorer?query=runner&sort=timestamp",
+        "runner",
+        Some("timestamp")
+    )]
+    #[case(
+        "https://app.datadoghq.com/event/explorer?query=runner&sort=bogus",
+        "runner",
+        None
+    )]
+    #[case(
+        "https://app.datadoghq.com/event/explorer?query=runner&service=web",
+        "runner service:web",
+        None
+    )]
+    #[case(
+        "https://app.datadoghq.com/event/explorer?service=web&sort=asc",
+        "service:web",
+        Some("timestamp")
+    )]
+    #[case(
+        "https://app.datadoghq.com/event/explorer?query=service%3Aweb&service=web",
+        "service:web",
+        None
+    )]
+    fn test_parse_events_url_with_facets(
+        #[case] url: &str,
+        #[case] expected_query: &str,
+        #[case] expected_sort: Option<&str>,
+    ) {
+        let result = parse_datadog_url(url).expect("should parse successfully");
+
+        match result.kind {
+            ResourceKind::Events(query) => {
+                assert_eq!(query.query, expected_query);
+                assert_eq!(query.sort.as_deref(), expected_sort);
+            }
+            _ => panic!("Expected Events resource"),
+        }
+    }
+
+    #[rstest]
+    #[case("https://app.datadoghq.com/monitors/12345", 12345)]
+    #[case("https://app.datadoghq.com/monitors/12345/edit", 12345)]
+    #[case(
+        "https://app.datadoghq.com/monitors/67890?from_ts=1704067200000",
+        67890
+    )]
+    fn test_parse_valid_monitor_url(#[case] url: &str, #[case] expected_id: u64) {
+        let result = parse_datadog_url(url).expect("should parse successfully");
+
+        match result.kind {
+            ResourceKind::Monitor { id } => assert_eq!(id, expected_id),
+            _ => panic!("Expected Monitor resource"),
+        }
+    }
+
+    #[rstest]
+    #[case("https://app.datadoghq.com/dashboard/abc-def-ghi", "abc-def-ghi")]
+    #[case(
+        "https://app.datadoghq.com/dashboard/abc-def-ghi/my-service-overview?from_ts=1704067200000",
+        "abc-def-ghi"
+    )]
+    fn test_parse_valid_dashboard_url(#[case] url: &str, #[case] expected_id: &str) {
+        let result = parse_datadog_url(url).expect("should parse successfully");
+
+        match result.kind {
+            ResourceKind::Dashboard { id } => assert_eq!(id, expected_id),
+            _ => panic!("Expected Dashboard resource"),
+        }
+    }
+
+    #[rstest]
+    #[case(
+        "https://app.datadoghq.com/apm/traces?query=service%3Aweb",
+        "service:web",
+        "now-15m",
+        "now"
+    )]
+    #[case("https://app.datadoghq.com/apm/traces", "*", "now-15m", "now")]
+    #[case(
+        "https://app.datadoghq.com/apm/traces?query=env%3Aprod&from_ts=1704067200000&to_ts=1704153600000",
+        "env:prod",
+        "2024-01-01",
+        "2024-01-02"
+    )]
+    fn test_parse_valid_trace_url(
+        #[case] url: &str,
+        #[case] expected_query: &str,
+        #[case] from_contains: &str,
+        #[case] to_contains: &str,
+    ) {
+        let result = parse_datadog_url(url).expect("should parse successfully");
+
+        match result.kind {
+            ResourceKind::Trace(query) => {
+                assert_eq!(query.query, expected_query);
+                assert!(query.from.contains(from_contains));
+                assert!(query.to.contains(to_contains));
+                assert_eq!(query.limit, Some(100));
+            }
+            _ => panic!("Expected Trace resource"),
+        }
+    }
+
+    #[rstest]
+    #[case("https://app.datadoghq.com/logs", "datadoghq.com")]
+    #[case("https://app.datadoghq.eu/event/explorer", "datadoghq.eu")]
+    #[case("https://us3.datadoghq.com/monitors/12345", "us3.datadoghq.com")]
+    #[case("https://us5.datadoghq.com/logs", "us5.datadoghq.com")]
+    #[case("https://app.ddog-gov.com/logs", "ddog-gov.com")]
+    fn test_parse_url_site(#[case] url: &str, #[case] expected: &str) {
+        let result = parse_datadog_url(url).expect("should parse successfully");
+
+        assert_eq!(result.site, expected);
+    }
+
     #[rstest]
     #[case("https://example.com/logs", "must be a Datadog URL")]
     #[case("https://google.com/logs", "must be a Datadog URL")]
-    #[case("https://app.datadoghq.com/apm/traces", "Unsupported Datadog resource")]
+    #[case("https://datadoghq.com.example.com/logs", "must be a Datadog URL")]
+    #[case("https://notdatadoghq.com/logs", "must be a Datadog URL")]
+    #[case("https://app.datadoghq.co/logs", "must be a Datadog URL")]
+    #[case(
+        "https://app.datadoghq.com/apm/services",
+        "Unsupported Datadog resource"
+    )]
     #[case("https://app.datadoghq.com/metrics", "Unsupported Datadog resource")]
+    #[case("https://app.datadoghq.com/monitors", "missing a monitor ID")]
+    #[case("https://app.datadoghq.com/monitors/manage", "Invalid monitor ID")]
+    #[case("https://app.datadoghq.com/dashboard", "missing a dashboard ID")]
     fn test_reject_invalid_urls(#[case] url: &str, #[case] error_contains: &str) {
         let result = parse_datadog_url(url);
 